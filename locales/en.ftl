@@ -0,0 +1,244 @@
+# Every message here must be in every other catalog too, or the tests fail.
+
+## Header
+
+nav-catalog = catalog
+nav-archive = archive
+nav-users = users
+nav-preferences = preferences
+theme = Theme
+theme-system = system
+theme-light = light
+theme-dark = dark
+theme-high-contrast = high contrast
+
+## Page titles, shown after "clovers ::"
+
+title-posts = posts
+title-replies = replies
+title-history = history
+title-catalog = catalog
+title-archive = archive
+title-users = users
+title-preferences = preferences
+
+## Home page
+
+make-a-post = Make a Post
+recent-posts = Recent Posts
+view-more = View More
+
+## Forms
+
+name-optional = Name (optional)
+anonymous = Anonymous
+subject-optional = Subject (optional)
+content = Content
+content-placeholder = What's on your mind?
+password-optional = Password (optional)
+password-placeholder = Leave blank to use this browser's
+cancel = Cancel
+post = Post
+reply = Reply
+edit = Edit
+delete = Delete
+save = Save
+
+## Posts
+
+posted = Posted
+archived = archived
+edited = (edited)
+in-reply-to = in reply to
+deleted = [deleted]
+removed = [removed]
+view-replies = View Replies
+reply-count =
+    { $count ->
+        [one] { $count } reply
+       *[other] { $count } replies
+    }
+last-reply = last
+catalog-reply-count = R: { $count }
+bumped = bumped
+
+## Replies
+
+replies = Replies
+no-replies = No replies yet.
+view-nested = nested
+view-flat = flat
+closed-to-replies = This post no longer takes replies.
+load-replies =
+    { $count ->
+        [one] Load { $count } Reply
+       *[other] Load { $count } Replies
+    }
+edit-history-of = Edit history of
+edited-at = Edited
+
+## Catalog
+
+catalog = Catalog
+sort-by = Sort by
+sort-bumped = last bump
+sort-created = creation date
+sort-replies = reply count
+filter-threads = Filter threads
+no-threads = No threads yet.
+
+## Archive
+
+archive = Archive
+archive-description = Threads that fell off the board. They can still be read, but not replied to.
+
+## Profiles
+
+title-tripcode = tripcode
+first-seen = First seen
+last-seen = last seen
+post-count =
+    { $count ->
+        [one] { $count } post
+       *[other] { $count } posts
+    }
+thread-count =
+    { $count ->
+        [one] { $count } thread
+       *[other] { $count } threads
+    }
+names-with-tripcode = Every name used with this tripcode
+activity = Activity
+activity-description = Posts per week, over the last { $weeks } weeks.
+most-active-threads = Most active threads
+posts = Posts
+names-used-with = Names used with
+last-used = last used
+
+## User search
+
+users = Users
+search-name-like = Name contains
+search-tripcode = Tripcode
+search = Search
+posts-with-tripcode = Posts with the tripcode
+posters-named-like = Posters whose name contains “{ $pattern }”
+active-tripcodes = Active tripcodes
+active-tripcodes-description = Tripcodes that posted in the last { $days } days, most recent first.
+no-results = Nothing found.
+
+## Pagination
+
+page-of = Page { $page } of { $pages }
+previous-page = Previous
+next-page = Next
+
+## Preferences
+
+preferences = Preferences
+preferences-description = These are kept in cookies, so they only apply to this browser.
+preferences-saved = Saved.
+default-name = Default name
+default-name-help = Filled into every form. Use name#secret for a tripcode.
+language = Language
+language-automatic = Automatic ({ $language })
+timezone = Timezone
+timezone-automatic = Automatic ({ $timezone })
+replies-view = Replies view
+lazy-threshold = Lazy-load threshold
+lazy-threshold-help = Deeper replies load on their own as they scroll into view, unless there are more than this many.
+hide-images = Hide images
+hide-images-help = Links to images stay links, and the images aren't loaded from where they're hosted.
+
+## Identities, made and used by static/identity.js
+
+identity = Identity
+identity-description = Sign your posts with a key kept in this browser, instead of a tripcode secret. Only its public half is ever sent, so it can't be guessed. Signed posts show its fingerprint after a key.
+identity-none = This browser has no identity yet.
+identity-fingerprint = Fingerprint
+create-identity = Create an identity
+forget-identity = Forget this identity
+forget-identity-confirm = Nothing can be signed with this identity once it's forgotten, not even from another browser. Forget it anyway?
+identity-unavailable = This browser can't sign with an identity.
+sign-with-identity = Sign with my identity
+
+## Rejected submissions
+
+error-rate-limited = You're posting too quickly, try again in a few seconds.
+error-archived = This thread is archived and can no longer be changed.
+error-deleted = This post was deleted.
+error-wrong-password = Wrong password.
+error-too-many-attempts = Too many wrong passwords were tried from here. Try again in a few minutes.
+error-edit-window-closed = This post is too old to be edited.
+error-unknown-identity = This identity isn't known here. Create a new one in the preferences.
+error-bad-signature = The signature doesn't match this post. Check that your clock is right, and try again.
+error-name-too-long =
+    { $max ->
+        [one] Names can be at most { $max } character long.
+       *[other] Names can be at most { $max } characters long.
+    }
+error-name-invalid-characters = Names can't contain control, invisible or direction-changing characters.
+error-subject-too-long =
+    { $max ->
+        [one] Subjects can be at most { $max } character long.
+       *[other] Subjects can be at most { $max } characters long.
+    }
+error-subject-invalid-characters = Subjects can't contain control characters.
+error-content-empty = Write something first.
+error-content-too-long =
+    { $max ->
+        [one] Posts can be at most { $max } character long.
+       *[other] Posts can be at most { $max } characters long.
+    }
+error-content-invalid-characters = Posts can't contain control characters.
+
+## Dates, worded like static/time.js has Intl.DateTimeFormat word them. time-date-format is
+## a chrono strftime pattern, in which %b stands for time-month.
+
+time-date-format = %b %-d, %Y, %-I:%M:%S %p
+time-month =
+    { $month ->
+        [1] Jan
+        [2] Feb
+        [3] Mar
+        [4] Apr
+        [5] May
+        [6] Jun
+        [7] Jul
+        [8] Aug
+        [9] Sep
+        [10] Oct
+        [11] Nov
+       *[12] Dec
+    }
+
+## Relative times, kept current by static/time.js
+
+time-just-now = just now
+time-ago = { $duration } ago
+time-in = in { $duration }
+time-seconds =
+    { $count ->
+        [one] { $count } second
+       *[other] { $count } seconds
+    }
+time-minutes =
+    { $count ->
+        [one] { $count } minute
+       *[other] { $count } minutes
+    }
+time-hours =
+    { $count ->
+        [one] { $count } hour
+       *[other] { $count } hours
+    }
+time-days =
+    { $count ->
+        [one] { $count } day
+       *[other] { $count } days
+    }
+time-weeks =
+    { $count ->
+        [one] { $count } week
+       *[other] { $count } weeks
+    }
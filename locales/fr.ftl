@@ -0,0 +1,244 @@
+# Every message here must be in every other catalog too, or the tests fail.
+
+## Header
+
+nav-catalog = catalogue
+nav-archive = archives
+nav-users = utilisateurs
+nav-preferences = préférences
+theme = Thème
+theme-system = système
+theme-light = clair
+theme-dark = sombre
+theme-high-contrast = contraste élevé
+
+## Page titles, shown after "clovers ::"
+
+title-posts = messages
+title-replies = réponses
+title-history = historique
+title-catalog = catalogue
+title-archive = archives
+title-users = utilisateurs
+title-preferences = préférences
+
+## Home page
+
+make-a-post = Publier un message
+recent-posts = Messages récents
+view-more = Voir plus
+
+## Forms
+
+name-optional = Nom (facultatif)
+anonymous = Anonyme
+subject-optional = Sujet (facultatif)
+content = Contenu
+content-placeholder = À quoi pensez-vous ?
+password-optional = Mot de passe (facultatif)
+password-placeholder = Laisser vide pour utiliser celui de ce navigateur
+cancel = Annuler
+post = Publier
+reply = Répondre
+edit = Modifier
+delete = Supprimer
+save = Enregistrer
+
+## Posts
+
+posted = Publié
+archived = archivé
+edited = (modifié)
+in-reply-to = en réponse à
+deleted = [supprimé]
+removed = [retiré]
+view-replies = Voir les réponses
+reply-count =
+    { $count ->
+        [one] { $count } réponse
+       *[other] { $count } réponses
+    }
+last-reply = dernière
+catalog-reply-count = R : { $count }
+bumped = remonté
+
+## Replies
+
+replies = Réponses
+no-replies = Pas encore de réponses.
+view-nested = imbriquées
+view-flat = à plat
+closed-to-replies = Ce message n'accepte plus de réponses.
+load-replies =
+    { $count ->
+        [one] Charger { $count } réponse
+       *[other] Charger { $count } réponses
+    }
+edit-history-of = Historique des modifications de
+edited-at = Modifié
+
+## Catalog
+
+catalog = Catalogue
+sort-by = Trier par
+sort-bumped = dernière activité
+sort-created = date de création
+sort-replies = nombre de réponses
+filter-threads = Filtrer les fils
+no-threads = Pas encore de fils.
+
+## Archive
+
+archive = Archives
+archive-description = Les fils tombés du tableau. On peut encore les lire, mais plus y répondre.
+
+## Profiles
+
+title-tripcode = tripcode
+first-seen = Vu pour la première fois
+last-seen = pour la dernière fois
+post-count =
+    { $count ->
+        [one] { $count } message
+       *[other] { $count } messages
+    }
+thread-count =
+    { $count ->
+        [one] { $count } fil
+       *[other] { $count } fils
+    }
+names-with-tripcode = Tous les noms utilisés avec ce tripcode
+activity = Activité
+activity-description = Messages par semaine, sur les { $weeks } dernières semaines.
+most-active-threads = Fils les plus actifs
+posts = Messages
+names-used-with = Noms utilisés avec
+last-used = dernière utilisation
+
+## User search
+
+users = Utilisateurs
+search-name-like = Le nom contient
+search-tripcode = Tripcode
+search = Rechercher
+posts-with-tripcode = Messages avec le tripcode
+posters-named-like = Auteurs dont le nom contient « { $pattern } »
+active-tripcodes = Tripcodes actifs
+active-tripcodes-description = Les tripcodes qui ont publié ces { $days } derniers jours, du plus récent au plus ancien.
+no-results = Aucun résultat.
+
+## Pagination
+
+page-of = Page { $page } sur { $pages }
+previous-page = Précédente
+next-page = Suivante
+
+## Preferences
+
+preferences = Préférences
+preferences-description = Elles sont gardées dans des cookies, et ne valent donc que pour ce navigateur.
+preferences-saved = Enregistré.
+default-name = Nom par défaut
+default-name-help = Rempli dans chaque formulaire. Utilisez nom#secret pour un tripcode.
+language = Langue
+language-automatic = Automatique ({ $language })
+timezone = Fuseau horaire
+timezone-automatic = Automatique ({ $timezone })
+replies-view = Affichage des réponses
+lazy-threshold = Seuil de chargement différé
+lazy-threshold-help = Les réponses plus profondes se chargent d'elles-mêmes en défilant, sauf s'il y en a plus que ce nombre.
+hide-images = Masquer les images
+hide-images-help = Les liens vers des images restent des liens, et les images ne sont pas chargées depuis leur hébergeur.
+
+## Identities, made and used by static/identity.js
+
+identity = Identité
+identity-description = Signez vos messages avec une clé gardée dans ce navigateur, plutôt qu'avec le secret d'un tripcode. Seule sa moitié publique est envoyée, elle ne peut donc pas être devinée. Les messages signés montrent son empreinte après une clé.
+identity-none = Ce navigateur n'a pas encore d'identité.
+identity-fingerprint = Empreinte
+create-identity = Créer une identité
+forget-identity = Oublier cette identité
+forget-identity-confirm = Plus rien ne pourra être signé avec cette identité une fois oubliée, pas même depuis un autre navigateur. L'oublier quand même ?
+identity-unavailable = Ce navigateur ne peut pas signer avec une identité.
+sign-with-identity = Signer avec mon identité
+
+## Rejected submissions
+
+error-rate-limited = Vous publiez trop vite, réessayez dans quelques secondes.
+error-archived = Ce fil est archivé et ne peut plus être modifié.
+error-deleted = Ce message a été supprimé.
+error-wrong-password = Mot de passe incorrect.
+error-too-many-attempts = Trop de mots de passe incorrects ont été essayés d'ici. Réessayez dans quelques minutes.
+error-edit-window-closed = Ce message est trop ancien pour être modifié.
+error-unknown-identity = Cette identité est inconnue ici. Créez-en une nouvelle dans les préférences.
+error-bad-signature = La signature ne correspond pas à ce message. Vérifiez que votre horloge est à l'heure, et réessayez.
+error-name-too-long =
+    { $max ->
+        [one] Les noms ne peuvent pas dépasser { $max } caractère.
+       *[other] Les noms ne peuvent pas dépasser { $max } caractères.
+    }
+error-name-invalid-characters = Les noms ne peuvent pas contenir de caractères de contrôle, invisibles ou qui changent le sens d'écriture.
+error-subject-too-long =
+    { $max ->
+        [one] Les sujets ne peuvent pas dépasser { $max } caractère.
+       *[other] Les sujets ne peuvent pas dépasser { $max } caractères.
+    }
+error-subject-invalid-characters = Les sujets ne peuvent pas contenir de caractères de contrôle.
+error-content-empty = Écrivez d'abord quelque chose.
+error-content-too-long =
+    { $max ->
+        [one] Les messages ne peuvent pas dépasser { $max } caractère.
+       *[other] Les messages ne peuvent pas dépasser { $max } caractères.
+    }
+error-content-invalid-characters = Les messages ne peuvent pas contenir de caractères de contrôle.
+
+## Dates, worded like static/time.js has Intl.DateTimeFormat word them. time-date-format is
+## a chrono strftime pattern, in which %b stands for time-month.
+
+time-date-format = %-d %b %Y, %H:%M:%S
+time-month =
+    { $month ->
+        [1] janv.
+        [2] févr.
+        [3] mars
+        [4] avr.
+        [5] mai
+        [6] juin
+        [7] juil.
+        [8] août
+        [9] sept.
+        [10] oct.
+        [11] nov.
+       *[12] déc.
+    }
+
+## Relative times, kept current by static/time.js
+
+time-just-now = à l'instant
+time-ago = il y a { $duration }
+time-in = dans { $duration }
+time-seconds =
+    { $count ->
+        [one] { $count } seconde
+       *[other] { $count } secondes
+    }
+time-minutes =
+    { $count ->
+        [one] { $count } minute
+       *[other] { $count } minutes
+    }
+time-hours =
+    { $count ->
+        [one] { $count } heure
+       *[other] { $count } heures
+    }
+time-days =
+    { $count ->
+        [one] { $count } jour
+       *[other] { $count } jours
+    }
+time-weeks =
+    { $count ->
+        [one] { $count } semaine
+       *[other] { $count } semaines
+    }
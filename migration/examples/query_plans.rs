@@ -0,0 +1,111 @@
+//! Compares query plans and timings of the hot `post` queries before and after the index
+//! migration, on a generated SQLite database.
+//!
+//! ```sh
+//! cargo run --release --example query_plans -- 200000
+//! ```
+
+use std::time::{Duration, Instant};
+
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{Database, DatabaseConnection, DbBackend, Statement},
+};
+
+/// Migrations up to, but not including, `m20230901_000001_add_post_indexes`.
+const BASELINE_MIGRATIONS: u32 = 2;
+
+/// Just the index migration, so later ones don't skew the comparison.
+const INDEX_MIGRATIONS: u32 = 1;
+
+const ITERATIONS: u32 = 100;
+
+const QUERIES: [(&str, &str); 4] = [
+    (
+        "top-level posts",
+        "SELECT * FROM post WHERE parent_post_id IS NULL ORDER BY id DESC LIMIT 3",
+    ),
+    (
+        "replies",
+        "SELECT * FROM post WHERE parent_post_id = 5000 ORDER BY id",
+    ),
+    (
+        "reply count",
+        "SELECT COUNT(*) FROM post WHERE parent_post_id = 5000",
+    ),
+    (
+        "posts by name",
+        "SELECT * FROM post WHERE name = 'user42' ORDER BY id DESC",
+    ),
+];
+
+#[async_std::main]
+async fn main() -> Result<(), DbErr> {
+    let count: u32 = std::env::args()
+        .nth(1)
+        .map(|arg| arg.parse().expect("post count must be a number"))
+        .unwrap_or(100_000);
+
+    let db = Database::connect("sqlite::memory:").await?;
+
+    migration::Migrator::up(&db, Some(BASELINE_MIGRATIONS)).await?;
+    generate(&db, count).await?;
+
+    println!("== before ({count} posts) ==");
+    report(&db).await?;
+
+    migration::Migrator::up(&db, Some(INDEX_MIGRATIONS)).await?;
+
+    println!("== after ({count} posts) ==");
+    report(&db).await?;
+
+    Ok(())
+}
+
+/// Fills the table with threads of ten posts each: every tenth post starts a thread, and
+/// the nine after it reply to it. Names cycle through 500 posters, a third with tripcodes.
+async fn generate(db: &DatabaseConnection, count: u32) -> Result<(), DbErr> {
+    db.execute_unprepared(&format!(
+        "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < {count})
+        INSERT INTO post (name, hash, content, parent_post_id, created_at)
+        SELECT
+            'user' || (n % 500),
+            CASE WHEN n % 3 = 0 THEN randomblob(32) END,
+            'generated post #' || n,
+            CASE WHEN n < 10 OR n % 10 = 0 THEN NULL ELSE n / 10 * 10 END,
+            datetime('now', '-' || ({count} - n) || ' minutes')
+        FROM seq"
+    ))
+    .await?;
+
+    Ok(())
+}
+
+async fn report(db: &DatabaseConnection) -> Result<(), DbErr> {
+    for (label, sql) in QUERIES {
+        let plan = db
+            .query_all(Statement::from_string(
+                DbBackend::Sqlite,
+                format!("EXPLAIN QUERY PLAN {sql}"),
+            ))
+            .await?
+            .into_iter()
+            .map(|row| row.try_get::<String>("", "detail"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut elapsed = Duration::ZERO;
+        for _ in 0..ITERATIONS {
+            let start = Instant::now();
+            db.query_all(Statement::from_string(DbBackend::Sqlite, sql))
+                .await?;
+            elapsed += start.elapsed();
+        }
+
+        println!("{label}: {:?} avg", elapsed / ITERATIONS);
+        for step in plan {
+            println!("    {step}");
+        }
+    }
+
+    Ok(())
+}
@@ -2,6 +2,16 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20230815_000001_create_post_table;
 mod m20230819_163054_add_date_column;
+mod m20230901_000001_add_post_indexes;
+mod m20230905_000001_add_thread_stats;
+mod m20230910_000001_backfill_reply_dates;
+mod m20230915_000001_add_post_subject;
+mod m20230920_000001_add_post_archived_at;
+mod m20230925_000001_add_post_password;
+mod m20230930_000001_add_post_soft_delete;
+mod m20231005_000001_create_post_revision_table;
+mod m20231010_000001_create_identity_table;
+mod m20231015_000001_fix_post_column_types;
 
 pub struct Migrator;
 
@@ -11,6 +21,16 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20230815_000001_create_post_table::Migration),
             Box::new(m20230819_163054_add_date_column::Migration),
+            Box::new(m20230901_000001_add_post_indexes::Migration),
+            Box::new(m20230905_000001_add_thread_stats::Migration),
+            Box::new(m20230910_000001_backfill_reply_dates::Migration),
+            Box::new(m20230915_000001_add_post_subject::Migration),
+            Box::new(m20230920_000001_add_post_archived_at::Migration),
+            Box::new(m20230925_000001_add_post_password::Migration),
+            Box::new(m20230930_000001_add_post_soft_delete::Migration),
+            Box::new(m20231005_000001_create_post_revision_table::Migration),
+            Box::new(m20231010_000001_create_identity_table::Migration),
+            Box::new(m20231015_000001_fix_post_column_types::Migration),
         ]
     }
 }
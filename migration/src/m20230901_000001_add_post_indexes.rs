@@ -0,0 +1,154 @@
+use sea_orm_migration::{prelude::*, sea_orm::DbBackend};
+
+/// Upper bounds enforced by the database itself; the application validates tighter limits.
+const MAX_NAME_LENGTH: u32 = 256;
+const MAX_CONTENT_LENGTH: u32 = 65536;
+
+/// Tripcode hashes are this many bytes. MySQL can only index a prefix of a BLOB.
+const HASH_LENGTH: u32 = 32;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let hash = match manager.get_database_backend() {
+            DbBackend::MySql => (Post::Hash, HASH_LENGTH).into_index_column(),
+            _ => Post::Hash.into_index_column(),
+        };
+
+        // Replies of a post, in order (`get_replies`, and the count in `get_replies_lazy`).
+        // Top-level posts are the `parent_post_id IS NULL` prefix of the same index.
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-post-parent_post_id-id")
+                    .table(Post::Table)
+                    .col(Post::ParentPostId)
+                    .col(Post::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Posts by a poster (`search_user`).
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-post-name-hash")
+                    .table(Post::Table)
+                    .col(Post::Name)
+                    .col(hash.clone())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Posts by a tripcode, whatever name it was used with.
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-post-hash")
+                    .table(Post::Table)
+                    .col(hash)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-post-created_at")
+                    .table(Post::Table)
+                    .col(Post::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+
+        match manager.get_database_backend() {
+            // SQLite can't add constraints to an existing table, so the checks are triggers.
+            DbBackend::Sqlite => {
+                for (trigger, event) in [
+                    ("trg-post-check-insert", "INSERT"),
+                    ("trg-post-check-update", "UPDATE OF name, content"),
+                ] {
+                    db.execute_unprepared(&format!(
+                        r#"CREATE TRIGGER IF NOT EXISTS "{trigger}" BEFORE {event} ON post
+                        WHEN length(NEW.name) NOT BETWEEN 1 AND {MAX_NAME_LENGTH}
+                            OR length(NEW.content) NOT BETWEEN 1 AND {MAX_CONTENT_LENGTH}
+                        BEGIN
+                            SELECT RAISE(ABORT, 'post name or content has an invalid length');
+                        END"#
+                    ))
+                    .await?;
+                }
+            }
+            // `NOT VALID` leaves rows written before this migration alone.
+            DbBackend::Postgres => {
+                db.execute_unprepared(&format!(
+                    r#"ALTER TABLE post ADD CONSTRAINT "chk-post-length" CHECK (
+                        char_length(name) BETWEEN 1 AND {MAX_NAME_LENGTH}
+                        AND char_length(content) BETWEEN 1 AND {MAX_CONTENT_LENGTH}
+                    ) NOT VALID"#
+                ))
+                .await?;
+            }
+            DbBackend::MySql => {
+                db.execute_unprepared(&format!(
+                    r#"ALTER TABLE post ADD CONSTRAINT `chk-post-length` CHECK (
+                        CHAR_LENGTH(name) BETWEEN 1 AND {MAX_NAME_LENGTH}
+                        AND CHAR_LENGTH(content) BETWEEN 1 AND {MAX_CONTENT_LENGTH}
+                    )"#
+                ))
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => {
+                db.execute_unprepared(r#"DROP TRIGGER IF EXISTS "trg-post-check-insert""#)
+                    .await?;
+                db.execute_unprepared(r#"DROP TRIGGER IF EXISTS "trg-post-check-update""#)
+                    .await?;
+            }
+            DbBackend::Postgres => {
+                db.execute_unprepared(r#"ALTER TABLE post DROP CONSTRAINT "chk-post-length""#)
+                    .await?;
+            }
+            DbBackend::MySql => {
+                db.execute_unprepared("ALTER TABLE post DROP CHECK `chk-post-length`")
+                    .await?;
+            }
+        }
+
+        for index in [
+            "idx-post-parent_post_id-id",
+            "idx-post-name-hash",
+            "idx-post-hash",
+            "idx-post-created_at",
+        ] {
+            manager
+                .drop_index(Index::drop().name(index).table(Post::Table).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Post {
+    Table,
+    Id,
+    Name,
+    Hash,
+    ParentPostId,
+    CreatedAt,
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{prelude::DateTimeUtc, Statement},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only allows one column per `ALTER TABLE`.
+        for mut column in [
+            ColumnDef::new(Post::ReplyCount)
+                .integer()
+                .not_null()
+                .default(0)
+                .to_owned(),
+            ColumnDef::new(Post::DescendantCount)
+                .integer()
+                .not_null()
+                .default(0)
+                .to_owned(),
+            ColumnDef::new(Post::LastReplyAt)
+                .timestamp_with_time_zone()
+                .null()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Post::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        backfill(manager).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Post::ReplyCount, Post::DescendantCount, Post::LastReplyAt] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Post::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    replies: i32,
+    descendants: i32,
+    last_reply_at: Option<DateTimeUtc>,
+}
+
+/// Computes the statistics of every existing post in one pass.
+///
+/// Replies always have a higher id than their parent, so walking the posts from the newest
+/// one down means a post's own statistics are complete before they're folded into its
+/// parent's.
+pub(crate) async fn backfill(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let db = manager.get_connection();
+    let backend = manager.get_database_backend();
+
+    let rows = db
+        .query_all(
+            backend.build(
+                &Query::select()
+                    .columns([Post::Id, Post::ParentPostId, Post::CreatedAt])
+                    .from(Post::Table)
+                    .order_by(Post::Id, Order::Desc)
+                    .to_owned(),
+            ),
+        )
+        .await?;
+
+    let mut stats = HashMap::<i32, Stats>::new();
+
+    for row in rows {
+        let id: i32 = row.try_get("", "id")?;
+        let Some(parent_id) = row.try_get::<Option<i32>>("", "parent_post_id")? else {
+            continue;
+        };
+        let created_at: DateTimeUtc = row.try_get("", "created_at")?;
+
+        let own = stats.get(&id);
+        let descendants = own.map_or(0, |own| own.descendants);
+        let latest = own
+            .and_then(|own| own.last_reply_at)
+            .map_or(created_at, |last| last.max(created_at));
+
+        let parent = stats.entry(parent_id).or_default();
+        parent.replies += 1;
+        parent.descendants += 1 + descendants;
+        parent.last_reply_at = parent.last_reply_at.max(Some(latest));
+    }
+
+    for (id, stats) in stats {
+        let statement: Statement = backend.build(
+            &Query::update()
+                .table(Post::Table)
+                .value(Post::ReplyCount, stats.replies)
+                .value(Post::DescendantCount, stats.descendants)
+                .value(Post::LastReplyAt, stats.last_reply_at)
+                .and_where(Expr::col(Post::Id).eq(id))
+                .to_owned(),
+        );
+
+        db.execute(statement).await?;
+    }
+
+    Ok(())
+}
+
+#[derive(DeriveIden)]
+enum Post {
+    Table,
+    Id,
+    ParentPostId,
+    CreatedAt,
+    ReplyCount,
+    DescendantCount,
+    LastReplyAt,
+}
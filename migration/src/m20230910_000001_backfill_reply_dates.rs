@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{prelude::DateTimeUtc, Statement},
+};
+
+use crate::m20230905_000001_add_thread_stats;
+
+/// Replies used to be inserted without a creation date, and got the column's default.
+const PLACEHOLDER_DATE: &str = "2020-06-09T04:20:00Z";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    /// Estimates the creation date of every reply stuck on the placeholder date.
+    ///
+    /// Ids are handed out in posting order, so a reply can't be older than the post right
+    /// before it, nor than its parent. The later of the two is used.
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        let placeholder: DateTimeUtc = PLACEHOLDER_DATE.parse().unwrap();
+
+        let rows = db
+            .query_all(
+                backend.build(
+                    &Query::select()
+                        .columns([Post::Id, Post::ParentPostId, Post::CreatedAt])
+                        .from(Post::Table)
+                        .order_by(Post::Id, Order::Asc)
+                        .to_owned(),
+                ),
+            )
+            .await?;
+
+        let mut dates = HashMap::<i32, DateTimeUtc>::new();
+        let mut previous: Option<DateTimeUtc> = None;
+
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let parent_id: Option<i32> = row.try_get("", "parent_post_id")?;
+            let mut created_at: DateTimeUtc = row.try_get("", "created_at")?;
+
+            if let (Some(parent_id), true) = (parent_id, created_at == placeholder) {
+                let parent_created_at = dates.get(&parent_id).copied();
+
+                if let Some(estimate) = previous.max(parent_created_at) {
+                    created_at = estimate;
+
+                    let statement: Statement = backend.build(
+                        &Query::update()
+                            .table(Post::Table)
+                            .value(Post::CreatedAt, estimate)
+                            .and_where(Expr::col(Post::Id).eq(id))
+                            .to_owned(),
+                    );
+
+                    db.execute(statement).await?;
+                }
+            }
+
+            if created_at != placeholder {
+                previous = Some(created_at);
+            }
+
+            dates.insert(id, created_at);
+        }
+
+        // `last_reply_at` was computed from the placeholder dates.
+        m20230905_000001_add_thread_stats::backfill(manager).await
+    }
+
+    /// The estimates can't be told apart from real dates, so they're left in place.
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Post {
+    Table,
+    Id,
+    ParentPostId,
+    CreatedAt,
+}
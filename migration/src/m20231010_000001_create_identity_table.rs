@@ -0,0 +1,134 @@
+use sea_orm_migration::{prelude::*, sea_orm::DbBackend};
+
+/// Fingerprints are this many bytes. MySQL can only index a prefix of a BLOB.
+const FINGERPRINT_LENGTH: u32 = 32;
+
+/// Ed25519 signatures are this many bytes.
+const SIGNATURE_LENGTH: u32 = 64;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let (fingerprint, signature) = match manager.get_database_backend() {
+            DbBackend::MySql => (
+                (Identity::Fingerprint, FINGERPRINT_LENGTH).into_index_column(),
+                (Post::Signature, SIGNATURE_LENGTH).into_index_column(),
+            ),
+            _ => (
+                Identity::Fingerprint.into_index_column(),
+                Post::Signature.into_index_column(),
+            ),
+        };
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Identity::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Identity::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Identity::Fingerprint)
+                            .blob(BlobSize::Blob(None))
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Identity::PublicKey)
+                            .blob(BlobSize::Blob(None))
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Identity::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Signed posts name their identity by fingerprint.
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-identity-fingerprint")
+                    .table(Identity::Table)
+                    .col(fingerprint)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Post::Table)
+                    .add_column(
+                        ColumnDef::new(Post::Signature)
+                            .blob(BlobSize::Blob(None))
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A signature can only be used once, or the same post could be made again in the
+        // signer's name. Every backend lets any number of unsigned posts be NULL.
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-post-signature")
+                    .table(Post::Table)
+                    .col(signature)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-post-signature")
+                    .table(Post::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Post::Table)
+                    .drop_column(Post::Signature)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Identity::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Post {
+    Table,
+    Signature,
+}
+
+#[derive(DeriveIden)]
+enum Identity {
+    Table,
+    Id,
+    Fingerprint,
+    PublicKey,
+    CreatedAt,
+}
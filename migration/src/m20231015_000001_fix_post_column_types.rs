@@ -0,0 +1,61 @@
+use sea_orm_migration::{prelude::*, sea_orm::DbBackend};
+
+/// `post.content` was created as a `varchar`, and `post.created_at` without a time zone,
+/// though dates are read back as UTC. SQLite doesn't enforce column types, so only the other
+/// backends need fixing.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => {}
+            // Every date was written in UTC.
+            DbBackend::Postgres => {
+                db.execute_unprepared(
+                    "ALTER TABLE post
+                        ALTER COLUMN content TYPE text,
+                        ALTER COLUMN created_at TYPE timestamp with time zone
+                            USING created_at AT TIME ZONE 'UTC',
+                        ALTER COLUMN created_at SET DEFAULT '2020-06-09 04:20:00+00'",
+                )
+                .await?;
+            }
+            // `DATETIME` is read back as UTC already. `TEXT` only holds 65535 bytes, short of
+            // the longest content allowed.
+            DbBackend::MySql => {
+                db.execute_unprepared("ALTER TABLE post MODIFY content MEDIUMTEXT NOT NULL")
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => {}
+            DbBackend::Postgres => {
+                db.execute_unprepared(
+                    "ALTER TABLE post
+                        ALTER COLUMN content TYPE varchar,
+                        ALTER COLUMN created_at TYPE timestamp without time zone
+                            USING created_at AT TIME ZONE 'UTC',
+                        ALTER COLUMN created_at SET DEFAULT '2020-06-09 04:20:00'",
+                )
+                .await?;
+            }
+            DbBackend::MySql => {
+                db.execute_unprepared("ALTER TABLE post MODIFY content VARCHAR(255) NOT NULL")
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+use std::{collections::HashMap, time::Duration};
+
+use sea_orm::{
+    entity::*, query::*, sea_query::Expr, ConnectionTrait, DatabaseConnection, DbErr,
+    TransactionTrait,
+};
+
+use crate::{
+    entities::{post, prelude::*},
+    reply_tree,
+    routes::posts::ThreadOrder,
+    thread_stats::{self, Removal},
+};
+
+/// How many posts are archived or deleted per statement, to stay clear of bind parameter
+/// limits.
+const ARCHIVE_BATCH_SIZE: usize = 500;
+
+/// The largest LIMIT every backend takes: it's a signed 64-bit integer on PostgreSQL and SQLite.
+const NO_LIMIT: u64 = i64::MAX as u64;
+
+/// Archives the least recently bumped threads until at most `limit` remain unarchived.
+/// Deleted threads count too, so they end up pruned like the rest.
+///
+/// Returns how many threads were archived.
+pub async fn archive_excess(db: &DatabaseConnection, limit: u64) -> Result<u64, DbErr> {
+    let unarchived = Post::find()
+        .filter(post::Column::ParentPostId.is_null())
+        .filter(post::Column::ArchivedAt.is_null());
+
+    // Not every backend takes an OFFSET without a LIMIT, so it's given one no thread count
+    // reaches.
+    let excess: Vec<i32> = ThreadOrder::Bumped
+        .apply(unarchived)
+        .select_only()
+        .column(post::Column::Id)
+        .offset(limit)
+        .limit(NO_LIMIT)
+        .into_tuple::<i32>()
+        .all(db)
+        .await?;
+
+    let now = chrono::Utc::now();
+
+    for &id in &excess {
+        let txn = db.begin().await?;
+        archive_thread(&txn, id, now).await?;
+        txn.commit().await?;
+    }
+
+    Ok(excess.len() as u64)
+}
+
+/// Marks the thread starting at `id`, and every reply in it, as archived.
+async fn archive_thread<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<(), DbErr> {
+    let mut ids = vec![id];
+    ids.extend(
+        reply_tree::load_descendants(db, id)
+            .await?
+            .into_iter()
+            .map(|reply| reply.id),
+    );
+
+    for batch in ids.chunks(ARCHIVE_BATCH_SIZE) {
+        Post::update_many()
+            .col_expr(post::Column::ArchivedAt, Expr::value(now))
+            .filter(post::Column::Id.is_in(batch.iter().copied()))
+            .exec(db)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Deletes threads that were archived more than `retention` ago, along with their replies.
+///
+/// Returns how many threads were deleted.
+pub async fn prune(db: &DatabaseConnection, retention: Duration) -> Result<u64, DbErr> {
+    let Ok(retention) = chrono::Duration::from_std(retention) else {
+        return Ok(0);
+    };
+
+    let cutoff = chrono::Utc::now() - retention;
+
+    let threads = Post::find()
+        .filter(post::Column::ParentPostId.is_null())
+        .filter(post::Column::ArchivedAt.lt(cutoff))
+        .all(db)
+        .await?;
+
+    for thread in &threads {
+        let txn = db.begin().await?;
+        delete_thread(&txn, thread.id).await?;
+        thread_stats::record_deletion(&txn, thread, Removal::Subtree).await?;
+        txn.commit().await?;
+    }
+
+    Ok(threads.len() as u64)
+}
+
+/// Deletes the thread starting at `id`, and every reply in it. Replies go deepest first, so
+/// there's nothing left for a deletion to cascade to: MySQL gives up on cascades more than 15
+/// levels deep.
+async fn delete_thread<C: ConnectionTrait>(db: &C, id: i32) -> Result<(), DbErr> {
+    let mut replies = reply_tree::load_descendants(db, id).await?;
+    // Replies always have a higher id than their parent, so parents come up first.
+    replies.sort_by_key(|reply| reply.id);
+
+    let mut depths = HashMap::from([(id, 0)]);
+    let mut levels: Vec<Vec<i32>> = Vec::new();
+
+    for reply in replies {
+        let depth = reply
+            .parent_post_id
+            .and_then(|parent_id| depths.get(&parent_id))
+            .map_or(1, |depth| depth + 1);
+
+        depths.insert(reply.id, depth);
+        if levels.len() < depth {
+            levels.resize_with(depth, Vec::new);
+        }
+        levels[depth - 1].push(reply.id);
+    }
+
+    for level in levels.iter().rev() {
+        for batch in level.chunks(ARCHIVE_BATCH_SIZE) {
+            Post::delete_many()
+                .filter(post::Column::Id.is_in(batch.iter().copied()))
+                .exec(db)
+                .await?;
+        }
+    }
+
+    Post::delete_by_id(id).exec(db).await?;
+
+    Ok(())
+}
+
+/// Periodically archives threads past `thread_limit`, and deletes the ones archived for longer
+/// than `retention`. Does nothing without an interval.
+pub async fn maintain(
+    db: DatabaseConnection,
+    thread_limit: Option<u64>,
+    retention: Option<Duration>,
+    interval: Option<Duration>,
+) {
+    let Some(interval) = interval else {
+        return;
+    };
+    if thread_limit.is_none() && retention.is_none() {
+        return;
+    }
+
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+
+        if let Some(limit) = thread_limit {
+            if let Err(err) = archive_excess(&db, limit).await {
+                eprintln!("archiving threads failed: {err}");
+            }
+        }
+
+        if let Some(retention) = retention {
+            if let Err(err) = prune(&db, retention).await {
+                eprintln!("pruning archived threads failed: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    use super::*;
+    use crate::{database, routes::posts};
+
+    async fn insert(
+        db: &DatabaseConnection,
+        parent_post_id: Option<i32>,
+        created_at: DateTime<Utc>,
+        last_reply_at: Option<DateTime<Utc>>,
+    ) -> i32 {
+        let post = post::ActiveModel {
+            content: ActiveValue::Set(String::from("content")),
+            name: ActiveValue::Set(String::from("name")),
+            parent_post_id: ActiveValue::Set(parent_post_id),
+            created_at: ActiveValue::Set(created_at),
+            last_reply_at: ActiveValue::Set(last_reply_at),
+            ..Default::default()
+        };
+
+        Post::insert(post).exec(db).await.unwrap().last_insert_id
+    }
+
+    async fn archived(db: &DatabaseConnection) -> Vec<i32> {
+        Post::find()
+            .select_only()
+            .column(post::Column::Id)
+            .filter(post::Column::ArchivedAt.is_not_null())
+            .order_by_asc(post::Column::Id)
+            .into_tuple()
+            .all(db)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn archives_the_least_recently_bumped_threads() {
+        let db = database::connect_test().await;
+        let start = Utc.with_ymd_and_hms(2023, 9, 1, 0, 0, 0).unwrap();
+        let hours = |hours| start + Duration::hours(hours);
+
+        let bumped = insert(&db, None, start, Some(hours(3))).await;
+        let stale = insert(&db, None, hours(1), Some(hours(2))).await;
+        let reply = insert(&db, Some(stale), hours(2), None).await;
+        let newest = insert(&db, None, hours(4), None).await;
+
+        assert_eq!(archive_excess(&db, 3).await.unwrap(), 0);
+        assert!(archived(&db).await.is_empty());
+
+        assert_eq!(archive_excess(&db, 2).await.unwrap(), 1);
+        assert_eq!(archived(&db).await, [stale, reply]);
+
+        let live: Vec<i32> = posts::threads(ThreadOrder::Bumped)
+            .select_only()
+            .column(post::Column::Id)
+            .into_tuple()
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(live, [newest, bumped]);
+    }
+
+    #[tokio::test]
+    async fn archives_deleted_threads_too() {
+        let db = database::connect_test().await;
+        let start = Utc.with_ymd_and_hms(2023, 9, 1, 0, 0, 0).unwrap();
+        let hours = |hours| start + Duration::hours(hours);
+
+        let deleted = insert(&db, None, start, None).await;
+        insert(&db, None, hours(1), None).await;
+        Post::update_many()
+            .col_expr(post::Column::DeletedAt, Expr::value(hours(2)))
+            .filter(post::Column::Id.eq(deleted))
+            .exec(&db)
+            .await
+            .unwrap();
+
+        assert_eq!(archive_excess(&db, 1).await.unwrap(), 1);
+        assert_eq!(archived(&db).await, [deleted]);
+    }
+
+    #[tokio::test]
+    async fn prunes_old_archives_with_every_reply() {
+        let db = database::connect_test().await;
+        let start = Utc.with_ymd_and_hms(2023, 9, 1, 0, 0, 0).unwrap();
+
+        let old = insert(&db, None, start, None).await;
+        // Deeper than MySQL follows cascades.
+        let mut parent = old;
+        for _ in 0..20 {
+            parent = insert(&db, Some(parent), start, None).await;
+        }
+        let recent = insert(&db, None, start, None).await;
+        insert(&db, Some(recent), start, None).await;
+
+        for (id, archived_at) in [(old, start), (recent, Utc::now())] {
+            Post::update_many()
+                .col_expr(post::Column::ArchivedAt, Expr::value(archived_at))
+                .filter(post::Column::Id.eq(id))
+                .exec(&db)
+                .await
+                .unwrap();
+        }
+
+        let retention = std::time::Duration::from_secs(24 * 60 * 60);
+        assert_eq!(prune(&db, retention).await.unwrap(), 1);
+
+        let left: Vec<Option<i32>> = Post::find()
+            .select_only()
+            .column(post::Column::ParentPostId)
+            .order_by_asc(post::Column::Id)
+            .into_tuple()
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(left, [None, Some(recent)]);
+    }
+}
@@ -0,0 +1,82 @@
+//! Where requests come from, for rate limiting.
+
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    async_trait,
+    extract::{rejection::ExtensionRejection, ConnectInfo, FromRequestParts},
+    http::{request::Parts, HeaderMap, HeaderName},
+};
+
+use crate::AppState;
+
+/// The address of the client that made a request.
+///
+/// Behind a reverse proxy, every connection comes from the proxy, so every poster would share
+/// one address. `CLOVERS_CLIENT_IP_HEADER` names the header the proxy passes the client's
+/// address in instead; requests without it fall back to the address they're connected from.
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = ExtensionRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let forwarded = state
+            .config
+            .client_ip_header
+            .as_ref()
+            .and_then(|header| from_header(&parts.headers, header));
+        if let Some(ip) = forwarded {
+            return Ok(Self(ip));
+        }
+
+        let ConnectInfo(addr) = ConnectInfo::<SocketAddr>::from_request_parts(parts, state).await?;
+
+        Ok(Self(addr.ip()))
+    }
+}
+
+/// The last address in `header`. Proxies append the address they were connected from to
+/// headers like `X-Forwarded-For`, so only the last one was added by the proxy in front of
+/// clovers: the ones before it are whatever the client claimed.
+fn from_header(headers: &HeaderMap, header: &HeaderName) -> Option<IpAddr> {
+    let value = headers.get_all(header).iter().next_back()?.to_str().ok()?;
+
+    value.rsplit(',').next()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("x-forwarded-for", value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn takes_the_address_the_proxy_added() {
+        let header = HeaderName::from_static("x-forwarded-for");
+
+        assert_eq!(
+            from_header(&headers(&["198.51.100.7, 192.0.2.1"]), &header),
+            Some("192.0.2.1".parse().unwrap())
+        );
+        assert_eq!(
+            from_header(&headers(&["198.51.100.7", "2001:db8::1"]), &header),
+            Some("2001:db8::1".parse().unwrap())
+        );
+        assert_eq!(
+            from_header(&headers(&["192.0.2.1, nonsense"]), &header),
+            None
+        );
+        assert_eq!(from_header(&headers(&[]), &header), None);
+    }
+}
@@ -0,0 +1,121 @@
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+use anyhow::Context;
+use axum::http::HeaderName;
+
+/// Runtime settings, read from the environment on startup.
+pub struct Config {
+    /// `DATABASE_URL`
+    pub database_url: String,
+    /// `CLOVERS_DB_MAX_CONNECTIONS`
+    pub db_max_connections: u32,
+    /// `CLOVERS_DB_BUSY_TIMEOUT`, in milliseconds.
+    pub db_busy_timeout: Duration,
+    /// `CLOVERS_DB_MAINTENANCE_INTERVAL`, in seconds. `0` never runs maintenance.
+    pub db_maintenance_interval: Option<Duration>,
+    /// `CLOVERS_DRAIN_TIMEOUT`, in seconds.
+    pub drain_timeout: Duration,
+    /// `CLOVERS_PROBE_DIR`: where `/readyz` checks that files can be written. Defaults to the
+    /// directory of the SQLite database, or else the system's temporary directory.
+    pub probe_dir: PathBuf,
+    /// `CLOVERS_REPLY_DEPTH`: how many levels of replies are rendered before loading lazily.
+    pub reply_depth: u32,
+    /// `CLOVERS_POST_COOLDOWN`: seconds a client has to wait between posts.
+    pub post_cooldown: Duration,
+    /// `CLOVERS_CLIENT_IP_HEADER`: the header a reverse proxy passes the client's address in,
+    /// like `X-Forwarded-For`. Unset uses the address requests are connected from.
+    pub client_ip_header: Option<HeaderName>,
+    /// `CLOVERS_MAX_NAME_LENGTH`, in characters.
+    pub max_name_length: usize,
+    /// `CLOVERS_MAX_SUBJECT_LENGTH`, in characters.
+    pub max_subject_length: usize,
+    /// `CLOVERS_MAX_CONTENT_LENGTH`, in characters.
+    pub max_content_length: usize,
+    /// `CLOVERS_EDIT_WINDOW`, in minutes: how long after posting authors can edit a post.
+    pub edit_window: Duration,
+    /// `CLOVERS_THREAD_LIMIT`: how many threads stay live before the least recently bumped
+    /// ones are archived. Unset, or `0`, never archives.
+    pub thread_limit: Option<u64>,
+    /// `CLOVERS_ARCHIVE_RETENTION`, in days: how long archived threads are kept before being
+    /// deleted. Unset keeps them forever.
+    pub archive_retention: Option<Duration>,
+    /// `CLOVERS_ARCHIVE_INTERVAL`, in seconds: how often threads are archived and pruned.
+    /// `0` never does.
+    pub archive_interval: Option<Duration>,
+}
+
+impl Config {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let database_url =
+            var("DATABASE_URL")?.unwrap_or_else(|| String::from("sqlite:./database.db?mode=rwc"));
+
+        Ok(Self {
+            probe_dir: var("CLOVERS_PROBE_DIR")?
+                .or_else(|| sqlite_dir(&database_url))
+                .unwrap_or_else(std::env::temp_dir),
+            database_url,
+            db_max_connections: var("CLOVERS_DB_MAX_CONNECTIONS")?.unwrap_or(8),
+            db_busy_timeout: Duration::from_millis(var("CLOVERS_DB_BUSY_TIMEOUT")?.unwrap_or(5000)),
+            db_maintenance_interval: var("CLOVERS_DB_MAINTENANCE_INTERVAL")?
+                .or(Some(60 * 60))
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+            drain_timeout: Duration::from_secs(var("CLOVERS_DRAIN_TIMEOUT")?.unwrap_or(30)),
+            reply_depth: var("CLOVERS_REPLY_DEPTH")?.unwrap_or(3),
+            post_cooldown: Duration::from_secs(var("CLOVERS_POST_COOLDOWN")?.unwrap_or(5)),
+            client_ip_header: var("CLOVERS_CLIENT_IP_HEADER")?,
+            // The database refuses names and subjects longer than 256 characters, and content
+            // longer than 65536, regardless.
+            max_name_length: var::<usize>("CLOVERS_MAX_NAME_LENGTH")?
+                .unwrap_or(64)
+                .min(256),
+            max_subject_length: var::<usize>("CLOVERS_MAX_SUBJECT_LENGTH")?
+                .unwrap_or(100)
+                .min(256),
+            max_content_length: var::<usize>("CLOVERS_MAX_CONTENT_LENGTH")?
+                .unwrap_or(10_000)
+                .min(65_536),
+            edit_window: Duration::from_secs(var("CLOVERS_EDIT_WINDOW")?.unwrap_or(15) * 60),
+            thread_limit: var("CLOVERS_THREAD_LIMIT")?.filter(|&limit| limit > 0),
+            archive_retention: var::<u64>("CLOVERS_ARCHIVE_RETENTION")?
+                .map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+            archive_interval: var("CLOVERS_ARCHIVE_INTERVAL")?
+                .or(Some(10 * 60))
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+        })
+    }
+}
+
+/// The directory holding the SQLite database at `url`, if it's one on disk.
+fn sqlite_dir(url: &str) -> Option<PathBuf> {
+    let path = url.strip_prefix("sqlite:")?;
+    let path = path.strip_prefix("//").unwrap_or(path);
+    let path = path.split('?').next().unwrap_or_default();
+
+    if path.is_empty() || path == ":memory:" {
+        return None;
+    }
+
+    match std::path::Path::new(path).parent()? {
+        // A bare file name is in the working directory.
+        dir if dir.as_os_str().is_empty() => Some(PathBuf::from(".")),
+        dir => Some(dir.to_path_buf()),
+    }
+}
+
+/// Parses the environment variable `key`, if it's set.
+fn var<T>(key: &str) -> anyhow::Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match std::env::var(key) {
+        Ok(value) => value
+            .parse()
+            .map(Some)
+            .with_context(|| format!("invalid value for {key}: {value:?}")),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(err) => Err(err).context(key.to_owned()),
+    }
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use sea_orm::{ConnectOptions, ConnectionTrait, DatabaseConnection, DbBackend};
+
+use crate::config::Config;
+
+/// Connects to the configured database, tuning SQLite for concurrent writers.
+pub async fn connect(config: &Config) -> anyhow::Result<DatabaseConnection> {
+    #[cfg(feature = "sqlite")]
+    if config.database_url.starts_with("sqlite:") {
+        return connect_sqlite(config).await;
+    }
+
+    let mut options = ConnectOptions::new(config.database_url.clone());
+    options.max_connections(config.db_max_connections);
+
+    Ok(sea_orm::Database::connect(options).await?)
+}
+
+#[cfg(feature = "sqlite")]
+async fn connect_sqlite(config: &Config) -> anyhow::Result<DatabaseConnection> {
+    use std::str::FromStr;
+
+    use sea_orm::SqlxSqliteConnector;
+    use sqlx::sqlite::{
+        SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous,
+    };
+
+    // WAL lets readers carry on while a post is being written, and the busy timeout makes
+    // concurrent writers wait for the lock instead of failing with "database is locked".
+    let options = SqliteConnectOptions::from_str(&config.database_url)?
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(config.db_busy_timeout)
+        .foreign_keys(true);
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(config.db_max_connections)
+        .connect_with(options)
+        .await?;
+
+    Ok(SqlxSqliteConnector::from_sqlx_sqlite_pool(pool))
+}
+
+/// Periodically refreshes the query planner's statistics and folds the WAL back into the
+/// database file. Only does anything on SQLite, and with an interval set.
+pub async fn maintain(db: DatabaseConnection, interval: Option<Duration>) {
+    let Some(interval) = interval else {
+        return;
+    };
+    if db.get_database_backend() != DbBackend::Sqlite {
+        return;
+    }
+
+    let mut ticker = tokio::time::interval(interval);
+
+    // The first tick completes immediately; there's nothing to do right after startup.
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+
+        for pragma in ["PRAGMA optimize", "PRAGMA wal_checkpoint(TRUNCATE)"] {
+            if let Err(err) = db.execute_unprepared(pragma).await {
+                eprintln!("database maintenance failed ({pragma}): {err}");
+            }
+        }
+    }
+}
+
+/// A freshly migrated database for tests: the one at `CLOVERS_TEST_DATABASE_URL`, so the
+/// suite can run against each backend, or else an in-memory SQLite database.
+///
+/// Every table is dropped first, so point it at a database of its own.
+#[cfg(test)]
+pub async fn connect_test() -> DatabaseConnection {
+    use migration::MigratorTrait;
+
+    let url = std::env::var("CLOVERS_TEST_DATABASE_URL")
+        .unwrap_or_else(|_| String::from("sqlite::memory:"));
+
+    let mut options = ConnectOptions::new(url);
+    // Each connection to `sqlite::memory:` would get an empty database of its own.
+    options.max_connections(1);
+
+    let db = sea_orm::Database::connect(options)
+        .await
+        .expect("Connecting to the test database failed");
+    migration::Migrator::fresh(&db)
+        .await
+        .expect("Migrating the test database failed");
+
+    db
+}
+
+#[cfg(test)]
+mod tests {
+    use migration::MigratorTrait;
+
+    #[tokio::test]
+    async fn migrations_roll_back_and_apply_again() {
+        let db = super::connect_test().await;
+
+        migration::Migrator::down(&db, None).await.unwrap();
+        assert!(migration::Migrator::get_applied_migrations(&db)
+            .await
+            .unwrap()
+            .is_empty());
+
+        migration::Migrator::up(&db, None).await.unwrap();
+        assert!(migration::Migrator::get_pending_migrations(&db)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
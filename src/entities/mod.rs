@@ -2,4 +2,6 @@
 
 pub mod prelude;
 
+pub mod identity;
 pub mod post;
+pub mod post_revision;
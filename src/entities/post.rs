@@ -14,6 +14,23 @@ pub struct Model {
     pub parent_post_id: Option<i32>,
     // Need to manually specify the column type because sea-orm-codegen cannot infer the type of `created_at` column
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub reply_count: i32,
+    pub descendant_count: i32,
+    pub last_reply_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub subject: Option<String>,
+    /// Set on every post of a thread once it's archived.
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// PHC string of the password that lets the author delete or edit the post.
+    pub password_hash: Option<String>,
+    /// Deleted posts stay in place as tombstones, so the replies to them survive.
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Who deleted the post: see [`crate::post_service::Deletion`].
+    pub deleted_by: Option<String>,
+    /// When the content was last edited; earlier versions are kept as revisions.
+    pub edited_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Ed25519 signature of a post made with an identity, whose fingerprint is then its hash.
+    #[sea_orm(column_type = "Binary(BlobSize::Blob(None))", nullable)]
+    pub signature: Option<Vec<u8>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -26,6 +43,14 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     SelfRef,
+    #[sea_orm(has_many = "super::post_revision::Entity")]
+    PostRevision,
+}
+
+impl Related<super::post_revision::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PostRevision.def()
+    }
 }
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -1,3 +1,5 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.2
 
+pub use super::identity::Entity as Identity;
 pub use super::post::Entity as Post;
+pub use super::post_revision::Entity as PostRevision;
@@ -0,0 +1,186 @@
+//! Translations of the interface. Messages live in one Fluent catalog per [`Locale`], under
+//! `locales/`, and are looked up by id.
+
+use std::sync::OnceLock;
+
+use anyhow::anyhow;
+use fluent::{concurrent::FluentBundle, FluentArgs, FluentResource};
+use fluent_langneg::{accepted_languages, negotiate_languages, NegotiationStrategy};
+use unic_langid::LanguageIdentifier;
+
+/// A language the interface is available in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    French,
+}
+
+/// Every catalog, in the order of [`Locale::ALL`].
+static CATALOGS: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+
+impl Locale {
+    pub const ALL: [Self; 2] = [Self::English, Self::French];
+
+    /// The language tag, as used in `lang` attributes.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::French => "fr",
+        }
+    }
+
+    /// The name of the language, in that language.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::French => "Français",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|locale| locale.as_str() == value)
+    }
+
+    /// The locale best matching an `Accept-Language` header, or else English.
+    pub fn negotiate(accept_language: &str) -> Self {
+        let requested = accepted_languages::parse(accept_language);
+        let available: Vec<LanguageIdentifier> =
+            Self::ALL.into_iter().map(Self::language_id).collect();
+
+        negotiate_languages(&requested, &available, None, NegotiationStrategy::Lookup)
+            .first()
+            .and_then(|language| Self::parse(language.language.as_str()))
+            .unwrap_or_default()
+    }
+
+    fn language_id(self) -> LanguageIdentifier {
+        self.as_str().parse().expect("Locale tags are valid")
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Self::English => include_str!("../locales/en.ftl"),
+            Self::French => include_str!("../locales/fr.ftl"),
+        }
+    }
+
+    /// The message `id`, in this locale.
+    pub fn text(self, id: &str) -> String {
+        self.format(id, None)
+    }
+
+    /// The message `id`, in this locale, filled in with `args`.
+    pub fn text_with(self, id: &str, args: &FluentArgs) -> String {
+        self.format(id, Some(args))
+    }
+
+    fn format(self, id: &str, args: Option<&FluentArgs>) -> String {
+        let bundle = &CATALOGS.get().expect("Catalogs are loaded at startup")[self as usize];
+
+        // The tests check that every catalog has every message, but an id can still be
+        // mistyped in the code, or an argument left out.
+        let pattern = bundle.get_message(id).and_then(|message| message.value());
+        debug_assert!(
+            pattern.is_some(),
+            "locales/{}.ftl has no message {id}",
+            self.as_str()
+        );
+        let Some(pattern) = pattern else {
+            return id.to_owned();
+        };
+
+        let mut errors = Vec::new();
+        let text = bundle
+            .format_pattern(pattern, args, &mut errors)
+            .into_owned();
+        debug_assert!(
+            errors.is_empty(),
+            "formatting {id} from locales/{}.ftl failed: {errors:?}",
+            self.as_str()
+        );
+
+        text
+    }
+}
+
+/// Parses every catalog. Must be called before any message is looked up.
+pub fn load() -> anyhow::Result<()> {
+    let mut catalogs = Vec::new();
+
+    for locale in Locale::ALL {
+        let file = format!("locales/{}.ftl", locale.as_str());
+
+        let resource = FluentResource::try_new(locale.source().to_owned())
+            .map_err(|(_, errors)| anyhow!("{file} doesn't parse: {errors:?}"))?;
+
+        let mut bundle = FluentBundle::new_concurrent(vec![locale.language_id()]);
+        // Isolation marks around arguments would show up in titles and attributes.
+        bundle.set_use_isolating(false);
+        bundle
+            .add_resource(resource)
+            .map_err(|errors| anyhow!("{file} has duplicate messages: {errors:?}"))?;
+
+        catalogs.push(bundle);
+    }
+
+    // Loading twice is harmless: the catalogs are the same.
+    let _ = CATALOGS.set(catalogs);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message_ids(locale: Locale) -> Vec<String> {
+        use fluent_syntax::{ast::Entry, parser};
+
+        parser::parse(locale.source())
+            .expect("Catalogs parse")
+            .body
+            .into_iter()
+            .filter_map(|entry| match entry {
+                Entry::Message(message) => Some(message.id.name.to_owned()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// So no page ends up half translated.
+    #[test]
+    fn catalogs_have_the_same_messages() {
+        load().unwrap();
+
+        for locale in Locale::ALL {
+            let ids = message_ids(locale);
+
+            for other in Locale::ALL {
+                let missing: Vec<_> = message_ids(other)
+                    .into_iter()
+                    .filter(|id| !ids.contains(id))
+                    .collect();
+
+                assert!(
+                    missing.is_empty(),
+                    "locales/{}.ftl is missing messages from locales/{}.ftl: {missing:?}",
+                    locale.as_str(),
+                    other.as_str(),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn negotiates_the_best_locale() {
+        assert_eq!(Locale::negotiate("fr-CA,en;q=0.5"), Locale::French);
+        assert_eq!(Locale::negotiate("de, fr;q=0.5"), Locale::French);
+        assert_eq!(Locale::negotiate("en-US,fr;q=0.5"), Locale::English);
+        assert_eq!(Locale::negotiate("de"), Locale::English);
+        assert_eq!(Locale::negotiate("garbage;q=nonsense,,"), Locale::English);
+        assert_eq!(Locale::negotiate(""), Locale::English);
+    }
+}
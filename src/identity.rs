@@ -0,0 +1,303 @@
+//! Identities posters can sign with instead of using a tripcode: an Ed25519 key pair made in
+//! the browser by `static/identity.js`. Only the public key is registered, so unlike a tripcode
+//! secret, nothing that could be brute-forced or leaked ever reaches the server.
+
+use std::collections::HashSet;
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use blake2::{Blake2s256, Digest};
+use chrono::Utc;
+use ed25519_dalek::{Signature, VerifyingKey};
+use sea_orm::{entity::*, query::*, sea_query::OnConflict, DatabaseConnection, DbBackend, DbErr};
+
+use crate::{
+    entities::{
+        identity, post,
+        prelude::{Identity, Post},
+    },
+    post_service::PostError,
+};
+
+/// How far, in seconds, the time a submission was signed at may be from the server's clock.
+const MAX_CLOCK_SKEW: i64 = 5 * 60;
+
+/// Keeps fingerprints apart from tripcode hashes.
+const FINGERPRINT_DOMAIN: &str = "clovers-identity#";
+
+/// The signature of a submission, from the fields `static/identity.js` fills in.
+pub struct Signed {
+    /// Fingerprint of the identity, base64url.
+    pub identity: String,
+    /// Ed25519 signature of [`message`], base64url.
+    pub signature: String,
+    /// Unix time the browser signed at.
+    pub signed_at: String,
+}
+
+impl Signed {
+    /// `None` if the submission wasn't signed.
+    pub fn from_form(identity: String, signature: String, signed_at: String) -> Option<Self> {
+        if identity.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            identity,
+            signature,
+            signed_at,
+        })
+    }
+}
+
+/// A signature that checked out.
+pub struct Verified {
+    /// Stored as the post's hash, so it shows and searches like a tripcode.
+    pub fingerprint: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+pub fn fingerprint(public_key: &VerifyingKey) -> Vec<u8> {
+    Blake2s256::new()
+        .chain_update(FINGERPRINT_DOMAIN)
+        .chain_update(public_key.as_bytes())
+        .finalize()
+        .to_vec()
+}
+
+/// Registers `public_key`, base64url, unless it already is. Returns its fingerprint, or `None`
+/// if it isn't a usable Ed25519 public key.
+pub async fn register(db: &DatabaseConnection, public_key: &str) -> Result<Option<Vec<u8>>, DbErr> {
+    let Some(public_key) = Base64UrlUnpadded::decode_vec(public_key)
+        .ok()
+        .and_then(|bytes| decode_public_key(&bytes))
+    else {
+        return Ok(None);
+    };
+
+    let fingerprint = fingerprint(&public_key);
+
+    let identity = identity::ActiveModel {
+        fingerprint: ActiveValue::Set(fingerprint.clone()),
+        public_key: ActiveValue::Set(public_key.as_bytes().to_vec()),
+        created_at: ActiveValue::Set(Utc::now()),
+        ..Default::default()
+    };
+
+    // The same key can be registered twice at once.
+    let mut on_conflict = OnConflict::column(identity::Column::Fingerprint);
+    match db.get_database_backend() {
+        // MySQL has no `DO NOTHING`: setting a column to what it already is comes closest.
+        DbBackend::MySql => on_conflict.update_column(identity::Column::Fingerprint),
+        _ => on_conflict.do_nothing(),
+    };
+
+    Identity::insert(identity)
+        .on_conflict(on_conflict)
+        .exec_without_returning(db)
+        .await?;
+
+    Ok(Some(fingerprint))
+}
+
+/// Which of `hashes` are fingerprints of identities, rather than tripcode hashes.
+pub async fn fingerprints(
+    db: &DatabaseConnection,
+    hashes: impl IntoIterator<Item = Vec<u8>>,
+) -> Result<HashSet<Vec<u8>>, DbErr> {
+    Ok(Identity::find()
+        .select_only()
+        .column(identity::Column::Fingerprint)
+        .filter(identity::Column::Fingerprint.is_in(hashes))
+        .into_tuple::<Vec<u8>>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect())
+}
+
+/// What gets signed: every field of the submission, what it replies to, and when it was
+/// signed. `static/identity.js` has to build the exact same text.
+pub fn message(
+    parent_post_id: Option<i32>,
+    signed_at: &str,
+    poster: &str,
+    subject: &str,
+    content: &str,
+) -> String {
+    let parent = parent_post_id.map(|id| id.to_string()).unwrap_or_default();
+    // Forms send line endings as `\r\n`, but the text is signed as typed, with `\n`.
+    let content = content.replace("\r\n", "\n");
+
+    format!("clovers post\n{parent}\n{signed_at}\n{poster}\n{subject}\n{content}")
+}
+
+/// Checks that `signed` is a recent signature of `message`, by a registered identity, that
+/// wasn't used before.
+pub async fn verify(
+    db: &DatabaseConnection,
+    signed: &Signed,
+    message: &str,
+) -> Result<Verified, PostError> {
+    let fingerprint =
+        Base64UrlUnpadded::decode_vec(&signed.identity).map_err(|_| PostError::UnknownIdentity)?;
+
+    let public_key = Identity::find()
+        .select_only()
+        .column(identity::Column::PublicKey)
+        .filter(identity::Column::Fingerprint.eq(fingerprint.clone()))
+        .into_tuple::<Vec<u8>>()
+        .one(db)
+        .await?
+        .and_then(|bytes| decode_public_key(&bytes))
+        .ok_or(PostError::UnknownIdentity)?;
+
+    let signed_at: i64 = signed
+        .signed_at
+        .parse()
+        .map_err(|_| PostError::BadSignature)?;
+    if (Utc::now().timestamp() - signed_at).abs() > MAX_CLOCK_SKEW {
+        return Err(PostError::BadSignature);
+    }
+
+    let signature = Base64UrlUnpadded::decode_vec(&signed.signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or(PostError::BadSignature)?;
+
+    public_key
+        .verify_strict(message.as_bytes(), &signature)
+        .map_err(|_| PostError::BadSignature)?;
+
+    // Otherwise anyone could post the same thing again in the signer's name. Signatures are
+    // unique in the database too, so a replay racing the original is turned away on insert.
+    let signature = signature.to_bytes().to_vec();
+    let replayed = Post::find()
+        .filter(post::Column::Signature.eq(signature.clone()))
+        .count(db)
+        .await?
+        > 0;
+    if replayed {
+        return Err(PostError::BadSignature);
+    }
+
+    Ok(Verified {
+        fingerprint,
+        signature,
+    })
+}
+
+fn decode_public_key(bytes: &[u8]) -> Option<VerifyingKey> {
+    let public_key = VerifyingKey::from_bytes(bytes.try_into().ok()?).ok()?;
+
+    // Weak keys have signatures anyone can forge.
+    (!public_key.is_weak()).then_some(public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+    use sea_orm::SqlErr;
+
+    use super::*;
+    use crate::database;
+
+    fn key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn sign(key: &SigningKey, signed_at: i64, content: &str) -> (Signed, String) {
+        let signed_at = signed_at.to_string();
+        let message = message(Some(1), &signed_at, "name", "", content);
+        let signature = key.sign(message.as_bytes()).to_bytes();
+
+        let signed = Signed {
+            identity: Base64UrlUnpadded::encode_string(&fingerprint(&key.verifying_key())),
+            signature: Base64UrlUnpadded::encode_string(&signature),
+            signed_at,
+        };
+
+        (signed, message)
+    }
+
+    async fn insert_signed(db: &DatabaseConnection, verified: &Verified) -> Result<(), DbErr> {
+        let post = post::ActiveModel {
+            content: ActiveValue::Set(String::from("content")),
+            name: ActiveValue::Set(String::from("name")),
+            hash: ActiveValue::Set(Some(verified.fingerprint.clone())),
+            signature: ActiveValue::Set(Some(verified.signature.clone())),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..Default::default()
+        };
+
+        Post::insert(post).exec(db).await.map(|_| ())
+    }
+
+    #[tokio::test]
+    async fn registers_keys_once() {
+        let db = database::connect_test().await;
+        let public_key = Base64UrlUnpadded::encode_string(key().verifying_key().as_bytes());
+
+        let first = register(&db, &public_key).await.unwrap();
+        let again = register(&db, &public_key).await.unwrap();
+
+        assert_eq!(first, Some(fingerprint(&key().verifying_key())));
+        assert_eq!(again, first);
+        assert_eq!(Identity::find().count(&db).await.unwrap(), 1);
+        assert_eq!(register(&db, "not a key").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn verifies_signatures() {
+        let db = database::connect_test().await;
+        let key = key();
+        let public_key = Base64UrlUnpadded::encode_string(key.verifying_key().as_bytes());
+        let now = Utc::now().timestamp();
+
+        let (signed, message) = sign(&key, now, "content");
+        assert!(matches!(
+            verify(&db, &signed, &message).await,
+            Err(PostError::UnknownIdentity)
+        ));
+
+        register(&db, &public_key).await.unwrap();
+
+        let verified = verify(&db, &signed, &message).await.unwrap();
+        assert_eq!(verified.fingerprint, fingerprint(&key.verifying_key()));
+
+        let (_, tampered) = sign(&key, now, "tampered");
+        assert!(matches!(
+            verify(&db, &signed, &tampered).await,
+            Err(PostError::BadSignature)
+        ));
+
+        let (stale, message) = sign(&key, now - MAX_CLOCK_SKEW - 60, "content");
+        assert!(matches!(
+            verify(&db, &stale, &message).await,
+            Err(PostError::BadSignature)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_replays() {
+        let db = database::connect_test().await;
+        let key = key();
+        let public_key = Base64UrlUnpadded::encode_string(key.verifying_key().as_bytes());
+        register(&db, &public_key).await.unwrap();
+
+        let (signed, message) = sign(&key, Utc::now().timestamp(), "content");
+        let verified = verify(&db, &signed, &message).await.unwrap();
+        insert_signed(&db, &verified).await.unwrap();
+
+        assert!(matches!(
+            verify(&db, &signed, &message).await,
+            Err(PostError::BadSignature)
+        ));
+
+        // Even a replay racing the original can't be stored.
+        let err = insert_signed(&db, &verified).await.unwrap_err();
+        assert!(matches!(
+            err.sql_err(),
+            Some(SqlErr::UniqueConstraintViolation(_))
+        ));
+    }
+}
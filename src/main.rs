@@ -1,33 +1,88 @@
 /// Auto-generated by sea-orm
 mod entities;
 
+mod archive;
+mod client_ip;
+mod config;
+mod database;
 mod error;
+mod i18n;
+mod identity;
+mod metrics;
+mod password;
+mod post_service;
 mod poster;
+mod preferences;
+mod profile;
 mod relative_time;
 mod render;
+mod reply_tree;
 mod routes;
+mod shutdown;
+mod thread_stats;
+mod validation;
+
+use std::{net::SocketAddr, sync::Arc};
+
+use tokio::sync::Notify;
 
 use axum_extra::routing::RouterExt;
 
 #[derive(Clone)]
 pub struct AppState {
     db: sea_orm::DatabaseConnection,
+    metrics: Arc<metrics::Metrics>,
+    config: Arc<config::Config>,
+    posts: Arc<post_service::PostService>,
 }
 
 /// Return type for fallible routes.
 type AppResult<T> = Result<T, error::AppError>;
 
-const DATABASE_URL: &str = "sqlite:./database.db?mode=rwc";
-
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     use migration::MigratorTrait;
 
+    let config = Arc::new(config::Config::from_env()?);
+    i18n::load()?;
+
     // == DATABASE ==
-    let db = sea_orm::Database::connect(DATABASE_URL).await?;
+    let db = database::connect(&config).await?;
     migration::Migrator::up(&db, None).await?;
 
-    let state = AppState { db };
+    let counters = Arc::<metrics::Metrics>::default();
+    let posts = post_service::PostService::new(db.clone(), &config).with_hook(counters.clone());
+
+    // == COMMANDS ==
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some((command, ids)) = args.split_first() {
+        let result = match command.as_str() {
+            "purge" => purge(&posts, ids).await,
+            _ => Err(anyhow::anyhow!("unknown command: {command}")),
+        };
+        db.close().await?;
+        return result;
+    }
+
+    // == BACKGROUND TASKS ==
+    let maintenance = tokio::spawn(database::maintain(
+        db.clone(),
+        config.db_maintenance_interval,
+    ));
+
+    let archiving = tokio::spawn(archive::maintain(
+        db.clone(),
+        config.thread_limit,
+        config.archive_retention,
+        config.archive_interval,
+    ));
+
+    let state = AppState {
+        db: db.clone(),
+        metrics: counters,
+        config: config.clone(),
+        posts: Arc::new(posts),
+    };
 
     // == ROUTES ==
     let app = axum::Router::new()
@@ -37,14 +92,74 @@ async fn main() -> anyhow::Result<()> {
         .typed_get(routes::replies::get_replies)
         .typed_post(routes::replies::make_reply)
         .typed_get(routes::replies::get_replies_lazy)
-        .typed_get(routes::user::search_user)
+        .typed_post(routes::replies::delete_post)
+        .typed_post(routes::replies::edit_post)
+        .typed_get(routes::replies::get_history)
+        .typed_get(routes::user::get_profile)
+        .typed_get(routes::user::get_tripcode_names)
+        .typed_get(routes::user::search_users)
+        .typed_post(routes::identity::register_identity)
+        .typed_get(routes::catalog::get_catalog)
+        .typed_get(routes::archive::get_archive)
+        .typed_get(routes::preferences::get_preferences)
+        .typed_post(routes::preferences::save_preferences)
+        .typed_post(routes::preferences::save_theme)
+        .typed_get(routes::health::healthz)
+        .typed_get(routes::health::readyz)
+        .typed_get(metrics::get_metrics)
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            metrics::track,
+        ))
         .nest_service("/static", tower_http::services::ServeDir::new("static"))
         .with_state(state);
 
     // == RUN ==
-    axum::Server::bind(&"0.0.0.0:3000".parse().unwrap())
-        .serve(app.into_make_service())
-        .await?;
+    let drain_timeout = config.drain_timeout;
+    let shutdown_requested = Arc::new(Notify::new());
+
+    let server = axum::Server::bind(&"0.0.0.0:3000".parse().unwrap())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown({
+            let shutdown_requested = shutdown_requested.clone();
+            async move {
+                shutdown::signal().await;
+                shutdown_requested.notify_one();
+            }
+        });
+
+    // Once a shutdown is requested, in-flight requests only get `drain_timeout` to finish.
+    tokio::select! {
+        result = server => result?,
+        _ = async {
+            shutdown_requested.notified().await;
+            tokio::time::sleep(drain_timeout).await;
+        } => eprintln!("drain timeout of {drain_timeout:?} elapsed, dropping remaining connections"),
+    }
+
+    maintenance.abort();
+    archiving.abort();
+    db.close().await?;
+
+    Ok(())
+}
+
+/// Erases the posts `ids` for good, for legal takedowns.
+async fn purge(posts: &post_service::PostService, ids: &[String]) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    anyhow::ensure!(!ids.is_empty(), "usage: clovers purge <post id>...");
+
+    for id in ids {
+        let id: i32 = id
+            .parse()
+            .with_context(|| format!("invalid post id: {id}"))?;
+
+        match posts.purge(id).await? {
+            Some(_) => println!("purged post {id}"),
+            None => eprintln!("no post {id}"),
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,281 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{MatchedPath, State},
+    http::{header, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::routing::TypedPath;
+use serde::Deserialize;
+
+use crate::{
+    entities::post,
+    post_service::{PostHook, Rejection},
+    AppState,
+};
+
+/// Upper bounds (in seconds) of the request latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/metrics")]
+pub struct MetricsPath;
+
+/// In-process counters, rendered in the Prometheus text format on `/metrics`.
+#[derive(Default)]
+pub struct Metrics {
+    routes: Mutex<BTreeMap<(String, String), RouteStats>>,
+    rejections: Mutex<BTreeMap<Rejection, u64>>,
+    posts_created: AtomicU64,
+    replies_created: AtomicU64,
+    lazy_loads: AtomicU64,
+}
+
+#[derive(Default)]
+struct RouteStats {
+    statuses: BTreeMap<u16, u64>,
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl RouteStats {
+    fn observe(&mut self, status: u16, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+
+        *self.statuses.entry(status).or_default() += 1;
+
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+impl PostHook for Metrics {
+    fn created(&self, post: &post::Model) {
+        match post.parent_post_id {
+            Some(_) => self.replies_created.fetch_add(1, Ordering::Relaxed),
+            None => self.posts_created.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    fn rejected(&self, reason: Rejection) {
+        *self.rejections.lock().unwrap().entry(reason).or_default() += 1;
+    }
+}
+
+impl Metrics {
+    pub fn lazy_load(&self) {
+        self.lazy_loads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn observe(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.routes
+            .lock()
+            .unwrap()
+            .entry((method.to_owned(), route.to_owned()))
+            .or_default()
+            .observe(status, elapsed);
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    fn render(&self, db: &sea_orm::DatabaseConnection) -> Result<String, std::fmt::Error> {
+        let mut out = String::new();
+
+        let routes = self.routes.lock().unwrap();
+
+        describe(
+            &mut out,
+            "clovers_http_requests_total",
+            "counter",
+            "Requests handled, by route and status.",
+        )?;
+        for ((method, route), stats) in routes.iter() {
+            for (status, count) in &stats.statuses {
+                writeln!(
+                    out,
+                    "clovers_http_requests_total{{method=\"{method}\",route=\"{route}\",status=\"{status}\"}} {count}"
+                )?;
+            }
+        }
+
+        describe(
+            &mut out,
+            "clovers_http_request_duration_seconds",
+            "histogram",
+            "Request latency, by route.",
+        )?;
+        for ((method, route), stats) in routes.iter() {
+            let labels = format!("method=\"{method}\",route=\"{route}\"");
+
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(stats.buckets) {
+                writeln!(
+                    out,
+                    "clovers_http_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {count}"
+                )?;
+            }
+            writeln!(
+                out,
+                "clovers_http_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                stats.count
+            )?;
+            writeln!(
+                out,
+                "clovers_http_request_duration_seconds_sum{{{labels}}} {}",
+                stats.sum
+            )?;
+            writeln!(
+                out,
+                "clovers_http_request_duration_seconds_count{{{labels}}} {}",
+                stats.count
+            )?;
+        }
+
+        drop(routes);
+
+        describe(
+            &mut out,
+            "clovers_posts_created_total",
+            "counter",
+            "Top-level posts created.",
+        )?;
+        writeln!(
+            out,
+            "clovers_posts_created_total {}",
+            self.posts_created.load(Ordering::Relaxed)
+        )?;
+
+        describe(
+            &mut out,
+            "clovers_replies_created_total",
+            "counter",
+            "Replies created.",
+        )?;
+        writeln!(
+            out,
+            "clovers_replies_created_total {}",
+            self.replies_created.load(Ordering::Relaxed)
+        )?;
+
+        describe(
+            &mut out,
+            "clovers_submissions_rejected_total",
+            "counter",
+            "Submissions turned away, by reason.",
+        )?;
+        for (reason, count) in self.rejections.lock().unwrap().iter() {
+            writeln!(
+                out,
+                "clovers_submissions_rejected_total{{reason=\"{}\"}} {count}",
+                reason.label()
+            )?;
+        }
+
+        describe(
+            &mut out,
+            "clovers_lazy_loads_total",
+            "counter",
+            "Lazy reply loads requested by clients.",
+        )?;
+        writeln!(
+            out,
+            "clovers_lazy_loads_total {}",
+            self.lazy_loads.load(Ordering::Relaxed)
+        )?;
+
+        if let Some((size, idle)) = pool_usage(db) {
+            describe(
+                &mut out,
+                "clovers_db_connections",
+                "gauge",
+                "Open database connections, by state.",
+            )?;
+            writeln!(out, "clovers_db_connections{{state=\"idle\"}} {idle}")?;
+            writeln!(
+                out,
+                "clovers_db_connections{{state=\"busy\"}} {}",
+                size.saturating_sub(idle)
+            )?;
+        }
+
+        Ok(out)
+    }
+}
+
+/// Returns the `(size, idle)` connection counts of the pool behind `db`.
+fn pool_usage(db: &sea_orm::DatabaseConnection) -> Option<(usize, usize)> {
+    use sea_orm::{ConnectionTrait, DbBackend};
+
+    match db.get_database_backend() {
+        #[cfg(feature = "sqlite")]
+        DbBackend::Sqlite => {
+            let pool = db.get_sqlite_connection_pool();
+            Some((pool.size() as usize, pool.num_idle()))
+        }
+        #[cfg(feature = "postgres")]
+        DbBackend::Postgres => {
+            let pool = db.get_postgres_connection_pool();
+            Some((pool.size() as usize, pool.num_idle()))
+        }
+        #[cfg(feature = "mysql")]
+        DbBackend::MySql => {
+            let pool = db.get_mysql_connection_pool();
+            Some((pool.size() as usize, pool.num_idle()))
+        }
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+fn describe(out: &mut String, name: &str, kind: &str, help: &str) -> std::fmt::Result {
+    writeln!(out, "# HELP {name} {help}")?;
+    writeln!(out, "# TYPE {name} {kind}")
+}
+
+/// Middleware recording the count and latency of every routed request.
+pub async fn track<B>(
+    State(state): State<AppState>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| request.uri().path().to_owned());
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+
+    state
+        .metrics
+        .observe(&method, &route, response.status().as_u16(), start.elapsed());
+
+    response
+}
+
+pub async fn get_metrics(_: MetricsPath, State(state): State<AppState>) -> Response {
+    let body = state
+        .metrics
+        .render(&state.db)
+        .expect("Writing to a String is infallible");
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
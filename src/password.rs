@@ -0,0 +1,47 @@
+//! Passwords letting authors delete or edit their own posts.
+
+use argon2::{
+    password_hash::{
+        rand_core::{OsRng, RngCore},
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+    },
+    Argon2,
+};
+use base64ct::Encoding;
+
+/// Hashes `password` into a PHC string.
+///
+/// Hashing is deliberately slow, so it runs off the async workers.
+pub async fn hash(password: String) -> String {
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .expect("Argon2 accepts any password with default parameters")
+            .to_string()
+    })
+    .await
+    .expect("Password hashing panicked")
+}
+
+/// Checks `password` against a PHC string made by [`hash`].
+pub async fn verify(hash: String, password: String) -> bool {
+    tokio::task::spawn_blocking(move || {
+        PasswordHash::new(&hash).is_ok_and(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+    })
+    .await
+    .expect("Password verification panicked")
+}
+
+/// A random password, for browsers whose author didn't pick one.
+pub fn token() -> String {
+    let mut bytes = [0; 24];
+    OsRng.fill_bytes(&mut bytes);
+
+    base64ct::Base64UrlUnpadded::encode_string(&bytes)
+}
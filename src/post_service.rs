@@ -0,0 +1,955 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr, SqlErr, TransactionTrait};
+
+use crate::{
+    config::Config,
+    entities::{post, post_revision, prelude::*},
+    identity::{self, Signed},
+    password,
+    poster::Poster,
+    thread_stats::{self, Removal},
+    validation::{self, FieldError, Limits, Valid},
+};
+
+/// What a post taken down is overwritten with. The column can't be empty.
+const REMOVED_CONTENT: &str = "[removed]";
+
+/// Once this many posters are being tracked, the ones past their cooldown are forgotten.
+const RATE_LIMIT_PRUNE_THRESHOLD: usize = 10_000;
+
+/// How many wrong passwords a client can give to delete or edit posts, per
+/// `PASSWORD_ATTEMPT_WINDOW`, before its attempts are turned away unchecked.
+const MAX_PASSWORD_ATTEMPTS: u32 = 10;
+
+const PASSWORD_ATTEMPT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// A post or reply, as submitted.
+pub struct NewPost {
+    pub content: String,
+    /// The raw name field, `name#secret`.
+    pub poster: String,
+    /// Only kept for posts starting a thread.
+    pub subject: Option<String>,
+    pub parent_post_id: Option<i32>,
+    /// Address the submission came from, for rate limiting.
+    pub client: Option<IpAddr>,
+    /// Lets the author delete or edit the post later.
+    pub password: Option<String>,
+    /// Set if the poster signed with an identity, which then stands in for their tripcode.
+    pub signed: Option<Signed>,
+}
+
+/// Why a submission was turned away before reaching the database.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rejection {
+    EmptyContent,
+    TooLong,
+    InvalidCharacters,
+    RateLimited,
+    Archived,
+    Deleted,
+    UnknownIdentity,
+    BadSignature,
+}
+
+impl Rejection {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::EmptyContent => "empty_content",
+            Self::TooLong => "too_long",
+            Self::InvalidCharacters => "invalid_characters",
+            Self::RateLimited => "rate_limit",
+            Self::Archived => "archived",
+            Self::Deleted => "deleted",
+            Self::UnknownIdentity => "unknown_identity",
+            Self::BadSignature => "bad_signature",
+        }
+    }
+}
+
+/// Who deleted a post, as stored in its `deleted_by` column.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Deletion {
+    /// The author, with the post's password. The content is kept, but no longer shown.
+    Author,
+    /// An operator purging the post, for a legal takedown. The content is gone for good.
+    Takedown,
+}
+
+impl Deletion {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Author => "author",
+            Self::Takedown => "takedown",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PostError {
+    /// One or more fields didn't pass validation.
+    Invalid(Vec<FieldError>),
+    RateLimited,
+    /// The reply was to a post in an archived thread.
+    Archived,
+    /// The reply was to a deleted post.
+    Deleted,
+    NotFound,
+    /// The password given doesn't match the post's, or it doesn't have one.
+    WrongPassword,
+    /// The client gave too many wrong passwords lately.
+    TooManyAttempts,
+    /// The post is too old to be edited.
+    EditWindowClosed,
+    /// The post was signed with an identity that isn't registered.
+    UnknownIdentity,
+    /// The signature is malformed, doesn't match the post, is stale, or was already used.
+    BadSignature,
+    Database(DbErr),
+}
+
+impl From<DbErr> for PostError {
+    fn from(err: DbErr) -> Self {
+        Self::Database(err)
+    }
+}
+
+/// Gets told what happened to posts once it's done, to act on it outside the database, like
+/// counting it in metrics. Hooks can't turn anything away; that's up to validation.
+pub trait PostHook: Send + Sync {
+    fn created(&self, _post: &post::Model) {}
+
+    /// Called once for every reason a submission was turned away.
+    fn rejected(&self, _reason: Rejection) {}
+
+    fn edited(&self, _post: &post::Model) {}
+
+    /// Called when a post is deleted by its author, or purged.
+    fn deleted(&self, _post: &post::Model) {}
+}
+
+/// Creates posts and replies: every way a post gets into the database goes through here.
+pub struct PostService {
+    db: DatabaseConnection,
+    hooks: Vec<Arc<dyn PostHook>>,
+    limits: Limits,
+    cooldown: Duration,
+    edit_window: Duration,
+    last_post_by: Mutex<HashMap<IpAddr, Instant>>,
+    failed_attempts_by: Mutex<HashMap<IpAddr, FailedAttempts>>,
+}
+
+/// Wrong passwords a client gave since `since`.
+struct FailedAttempts {
+    count: u32,
+    since: Instant,
+}
+
+/// A client's cooldown, started when its submission was let through. Dropping it ends the
+/// cooldown again, unless the post made it in.
+struct Slot<'a> {
+    service: &'a PostService,
+    client: IpAddr,
+    taken_at: Instant,
+    kept: bool,
+}
+
+impl Slot<'_> {
+    fn keep(mut self) {
+        self.kept = true;
+    }
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        if self.kept {
+            return;
+        }
+
+        let mut last_post_by = self.service.last_post_by.lock().unwrap();
+
+        // Unless the cooldown ran out and another post from the client took over since.
+        if last_post_by.get(&self.client) == Some(&self.taken_at) {
+            last_post_by.remove(&self.client);
+        }
+    }
+}
+
+impl PostService {
+    pub fn new(db: DatabaseConnection, config: &Config) -> Self {
+        Self {
+            db,
+            hooks: Vec::new(),
+            limits: Limits::from_config(config),
+            cooldown: config.post_cooldown,
+            edit_window: config.edit_window,
+            last_post_by: Mutex::default(),
+            failed_attempts_by: Mutex::default(),
+        }
+    }
+
+    /// Adds `hook` to the ones told about what happens to posts, after the others.
+    pub fn with_hook(mut self, hook: Arc<dyn PostHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    pub async fn create(&self, new_post: NewPost) -> Result<post::Model, PostError> {
+        let result = self.try_create(new_post).await;
+
+        let rejected = |reason| self.hooks.iter().for_each(|hook| hook.rejected(reason));
+
+        match &result {
+            Ok(post) => self.hooks.iter().for_each(|hook| hook.created(post)),
+            Err(PostError::Invalid(errors)) => {
+                for error in errors {
+                    rejected(error.reason);
+                }
+            }
+            Err(PostError::RateLimited) => rejected(Rejection::RateLimited),
+            Err(PostError::Archived) => rejected(Rejection::Archived),
+            Err(PostError::Deleted) => rejected(Rejection::Deleted),
+            Err(PostError::UnknownIdentity) => rejected(Rejection::UnknownIdentity),
+            Err(PostError::BadSignature) => rejected(Rejection::BadSignature),
+            Err(_) => {}
+        }
+
+        result
+    }
+
+    /// Registers the identity with `public_key`, base64url, like `identity::register`. Each
+    /// registration takes the client's turn to post, so identities can't be made in bulk.
+    pub async fn register_identity(
+        &self,
+        public_key: &str,
+        client: IpAddr,
+    ) -> Result<Option<Vec<u8>>, PostError> {
+        let slot = self.take_slot(client)?;
+
+        let fingerprint = identity::register(&self.db, public_key).await?;
+
+        if fingerprint.is_some() {
+            slot.keep();
+        }
+
+        Ok(fingerprint)
+    }
+
+    async fn try_create(&self, new_post: NewPost) -> Result<post::Model, PostError> {
+        let subject = match new_post.parent_post_id {
+            Some(_) => None,
+            None => new_post.subject.as_deref(),
+        };
+
+        let Valid {
+            poster,
+            subject,
+            content,
+        } = validation::validate(self.limits, &new_post.poster, subject, &new_post.content)
+            .map_err(PostError::Invalid)?;
+
+        // The signature covers the fields as they were sent, before any clean up.
+        let verified = match &new_post.signed {
+            Some(signed) => {
+                let message = identity::message(
+                    new_post.parent_post_id,
+                    &signed.signed_at,
+                    &new_post.poster,
+                    new_post.subject.as_deref().unwrap_or_default(),
+                    &new_post.content,
+                );
+                Some(identity::verify(&self.db, signed, &message).await?)
+            }
+            None => None,
+        };
+
+        let slot = match new_post.client {
+            Some(client) => Some(self.take_slot(client)?),
+            None => None,
+        };
+
+        let Poster { name, hash } = poster;
+        let (hash, signature) = match verified {
+            Some(verified) => (Some(verified.fingerprint), Some(verified.signature)),
+            None => (hash, None),
+        };
+
+        let password_hash = match new_post.password {
+            Some(password) => Some(password::hash(password).await),
+            None => None,
+        };
+
+        let post = post::ActiveModel {
+            content: ActiveValue::Set(content),
+            subject: ActiveValue::Set(subject),
+            name: ActiveValue::Set(name),
+            hash: ActiveValue::Set(hash),
+            signature: ActiveValue::Set(signature),
+            parent_post_id: ActiveValue::Set(new_post.parent_post_id),
+            created_at: ActiveValue::Set(chrono::Utc::now()),
+            password_hash: ActiveValue::Set(password_hash),
+            ..Default::default()
+        };
+
+        let txn = self.db.begin().await?;
+
+        if let Some(parent_id) = new_post.parent_post_id {
+            type Date = Option<chrono::DateTime<chrono::Utc>>;
+
+            let parent = Post::find_by_id(parent_id)
+                .select_only()
+                .columns([post::Column::ArchivedAt, post::Column::DeletedAt])
+                .into_tuple::<(Date, Date)>()
+                .one(&txn)
+                .await?;
+
+            match parent {
+                Some((Some(_), _)) => return Err(PostError::Archived),
+                Some((_, Some(_))) => return Err(PostError::Deleted),
+                _ => {}
+            }
+        }
+
+        // The signature is the only unique column that's set.
+        let post = match Post::insert(post).exec_with_returning(&txn).await {
+            Err(err) if matches!(err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                return Err(PostError::BadSignature)
+            }
+            result => result?,
+        };
+        thread_stats::record_reply(&txn, &post).await?;
+
+        txn.commit().await?;
+
+        // Only once it's in: a submission turned away doesn't start the cooldown.
+        if let Some(slot) = slot {
+            slot.keep();
+        }
+
+        Ok(post)
+    }
+
+    /// Deletes the post `id`, leaving a tombstone so the replies to it stay in place.
+    pub async fn delete(
+        &self,
+        id: i32,
+        password: String,
+        client: IpAddr,
+    ) -> Result<post::Model, PostError> {
+        let post = self.authorize(id, password, client).await?;
+
+        let mut post: post::ActiveModel = post.into();
+        post.deleted_at = ActiveValue::Set(Some(chrono::Utc::now()));
+        post.deleted_by = ActiveValue::Set(Some(String::from(Deletion::Author.as_str())));
+        post.password_hash = ActiveValue::Set(None);
+
+        let txn = self.db.begin().await?;
+
+        let post = post.update(&txn).await?;
+        thread_stats::record_deletion(&txn, &post, Removal::Tombstone).await?;
+
+        txn.commit().await?;
+
+        self.hooks.iter().for_each(|hook| hook.deleted(&post));
+
+        Ok(post)
+    }
+
+    /// Erases everything the post `id` said, in any of its revisions, and who said it,
+    /// whoever made it. Like any deleted post, it stays as a tombstone, so the replies to it
+    /// stay in place.
+    ///
+    /// Returns `None` if there's no such post.
+    pub async fn purge(&self, id: i32) -> Result<Option<post::Model>, DbErr> {
+        let Some(post) = Post::find_by_id(id).one(&self.db).await? else {
+            return Ok(None);
+        };
+
+        let was_deleted = post.deleted_at.is_some();
+        let deleted_at = post.deleted_at.unwrap_or_else(chrono::Utc::now);
+
+        let mut post: post::ActiveModel = post.into();
+        post.name = ActiveValue::Set(String::from(Poster::DEFAULT_NAME));
+        post.hash = ActiveValue::Set(None);
+        post.signature = ActiveValue::Set(None);
+        post.subject = ActiveValue::Set(None);
+        post.content = ActiveValue::Set(String::from(REMOVED_CONTENT));
+        post.password_hash = ActiveValue::Set(None);
+        post.deleted_at = ActiveValue::Set(Some(deleted_at));
+        post.deleted_by = ActiveValue::Set(Some(String::from(Deletion::Takedown.as_str())));
+
+        let txn = self.db.begin().await?;
+
+        // Earlier versions of the content have to go too.
+        PostRevision::delete_many()
+            .filter(post_revision::Column::PostId.eq(id))
+            .exec(&txn)
+            .await?;
+        let post = post.update(&txn).await?;
+        if !was_deleted {
+            thread_stats::record_deletion(&txn, &post, Removal::Tombstone).await?;
+        }
+
+        txn.commit().await?;
+
+        self.hooks.iter().for_each(|hook| hook.deleted(&post));
+
+        Ok(Some(post))
+    }
+
+    /// Replaces the content of the post `id`, if it was made less than `edit_window` ago. The
+    /// content it had is kept as a revision.
+    pub async fn edit(
+        &self,
+        id: i32,
+        password: String,
+        content: &str,
+        client: IpAddr,
+    ) -> Result<post::Model, PostError> {
+        let content =
+            validation::validate_content(self.limits, content).map_err(PostError::Invalid)?;
+
+        let post = self.authorize(id, password, client).await?;
+
+        let age = (chrono::Utc::now() - post.created_at)
+            .to_std()
+            .unwrap_or_default();
+        if age > self.edit_window {
+            return Err(PostError::EditWindowClosed);
+        }
+
+        let now = chrono::Utc::now();
+
+        let revision = post_revision::ActiveModel {
+            post_id: ActiveValue::Set(post.id),
+            content: ActiveValue::Set(post.content.clone()),
+            created_at: ActiveValue::Set(post.edited_at.unwrap_or(post.created_at)),
+            replaced_at: ActiveValue::Set(now),
+            ..Default::default()
+        };
+
+        // A signature only vouches for the content it was made over, so an edited post is
+        // no longer signed, nor shown with the identity it was signed with.
+        let signed = post.signature.is_some();
+
+        let mut post: post::ActiveModel = post.into();
+        post.content = ActiveValue::Set(content);
+        post.edited_at = ActiveValue::Set(Some(now));
+        if signed {
+            post.hash = ActiveValue::Set(None);
+            post.signature = ActiveValue::Set(None);
+        }
+
+        let txn = self.db.begin().await?;
+
+        PostRevision::insert(revision).exec(&txn).await?;
+        let post = post.update(&txn).await?;
+
+        txn.commit().await?;
+
+        self.hooks.iter().for_each(|hook| hook.edited(&post));
+
+        Ok(post)
+    }
+
+    /// Fetches the post `id`, if `password` is the one it was made with.
+    async fn authorize(
+        &self,
+        id: i32,
+        password: String,
+        client: IpAddr,
+    ) -> Result<post::Model, PostError> {
+        // Each check costs an Argon2 hash, and passwords could otherwise be guessed at will.
+        self.check_password_attempts(client)?;
+
+        let post = Post::find_by_id(id)
+            .one(&self.db)
+            .await?
+            .ok_or(PostError::NotFound)?;
+
+        if post.archived_at.is_some() {
+            return Err(PostError::Archived);
+        }
+
+        if post.deleted_at.is_some() {
+            return Err(PostError::Deleted);
+        }
+
+        let Some(hash) = post.password_hash.clone() else {
+            self.record_failed_attempt(client);
+            return Err(PostError::WrongPassword);
+        };
+
+        if !password::verify(hash, password).await {
+            self.record_failed_attempt(client);
+            return Err(PostError::WrongPassword);
+        }
+
+        Ok(post)
+    }
+
+    /// Allows one post per `cooldown` from each client. The cooldown starts right away, so
+    /// submissions made at the same time can't all get through.
+    fn take_slot(&self, client: IpAddr) -> Result<Slot<'_>, PostError> {
+        let now = Instant::now();
+        let mut last_post_by = self.last_post_by.lock().unwrap();
+
+        if let Some(last) = last_post_by.get(&client) {
+            if now.duration_since(*last) < self.cooldown {
+                return Err(PostError::RateLimited);
+            }
+        }
+
+        if last_post_by.len() > RATE_LIMIT_PRUNE_THRESHOLD {
+            last_post_by.retain(|_, last| now.duration_since(*last) < self.cooldown);
+        }
+
+        last_post_by.insert(client, now);
+
+        Ok(Slot {
+            service: self,
+            client,
+            taken_at: now,
+            kept: false,
+        })
+    }
+
+    /// Allows `MAX_PASSWORD_ATTEMPTS` wrong passwords per `PASSWORD_ATTEMPT_WINDOW` from each
+    /// client. Right ones don't count, but don't wipe the slate either: anyone can make a post
+    /// to give its password.
+    fn check_password_attempts(&self, client: IpAddr) -> Result<(), PostError> {
+        let failed_attempts_by = self.failed_attempts_by.lock().unwrap();
+
+        match failed_attempts_by.get(&client) {
+            Some(attempts)
+                if attempts.since.elapsed() < PASSWORD_ATTEMPT_WINDOW
+                    && attempts.count >= MAX_PASSWORD_ATTEMPTS =>
+            {
+                Err(PostError::TooManyAttempts)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn record_failed_attempt(&self, client: IpAddr) {
+        let now = Instant::now();
+        let mut failed_attempts_by = self.failed_attempts_by.lock().unwrap();
+
+        if failed_attempts_by.len() > RATE_LIMIT_PRUNE_THRESHOLD {
+            failed_attempts_by
+                .retain(|_, attempts| now.duration_since(attempts.since) < PASSWORD_ATTEMPT_WINDOW);
+        }
+
+        match failed_attempts_by.get_mut(&client) {
+            Some(attempts) if now.duration_since(attempts.since) < PASSWORD_ATTEMPT_WINDOW => {
+                attempts.count += 1;
+            }
+            _ => {
+                let attempts = FailedAttempts {
+                    count: 1,
+                    since: now,
+                };
+                failed_attempts_by.insert(client, attempts);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64ct::{Base64UrlUnpadded, Encoding};
+    use ed25519_dalek::SigningKey;
+
+    use super::*;
+    use crate::database;
+
+    fn config() -> Config {
+        Config {
+            database_url: String::from("sqlite::memory:"),
+            db_max_connections: 1,
+            db_busy_timeout: Duration::from_secs(5),
+            db_maintenance_interval: None,
+            drain_timeout: Duration::from_secs(30),
+            probe_dir: std::env::temp_dir(),
+            reply_depth: 3,
+            post_cooldown: Duration::from_secs(5),
+            client_ip_header: None,
+            max_name_length: 64,
+            max_subject_length: 100,
+            max_content_length: 10_000,
+            edit_window: Duration::from_secs(15 * 60),
+            thread_limit: None,
+            archive_retention: None,
+            archive_interval: None,
+        }
+    }
+
+    async fn service() -> PostService {
+        service_with(config()).await
+    }
+
+    async fn service_with(config: Config) -> PostService {
+        PostService::new(database::connect_test().await, &config)
+    }
+
+    fn client(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    fn from(address: &str, post: NewPost) -> NewPost {
+        NewPost {
+            client: Some(client(address)),
+            ..post
+        }
+    }
+
+    fn new_post(content: &str, parent_post_id: Option<i32>) -> NewPost {
+        NewPost {
+            content: content.to_owned(),
+            poster: String::from("name#secret"),
+            subject: Some(String::from("subject")),
+            parent_post_id,
+            client: None,
+            password: None,
+            signed: None,
+        }
+    }
+
+    async fn stored(service: &PostService, id: i32) -> post::Model {
+        Post::find_by_id(id)
+            .one(&service.db)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn creates_threads() {
+        let service = service().await;
+        let before = chrono::Utc::now();
+
+        let thread = service.create(new_post("hello", None)).await.unwrap();
+
+        assert_eq!(stored(&service, thread.id).await, thread);
+        assert_eq!(thread.content, "hello");
+        assert_eq!(thread.name, "name");
+        assert_eq!(
+            thread.hash,
+            Poster::with_name_and_secret("name", "secret").hash
+        );
+        assert_eq!(thread.subject.as_deref(), Some("subject"));
+        assert_eq!(thread.parent_post_id, None);
+        assert!(thread.created_at >= before);
+        assert_eq!(thread.reply_count, 0);
+        assert_eq!(thread.descendant_count, 0);
+        assert_eq!(thread.last_reply_at, None);
+    }
+
+    #[tokio::test]
+    async fn creates_replies() {
+        let service = service().await;
+        let thread = service.create(new_post("thread", None)).await.unwrap();
+        let before = chrono::Utc::now();
+
+        let reply = service
+            .create(new_post("reply", Some(thread.id)))
+            .await
+            .unwrap();
+        let nested = service
+            .create(new_post("nested", Some(reply.id)))
+            .await
+            .unwrap();
+
+        assert_eq!(stored(&service, nested.id).await, nested);
+        assert_eq!(nested.content, "nested");
+        assert_eq!(nested.name, "name");
+        assert_eq!(nested.parent_post_id, Some(reply.id));
+        // Only posts starting a thread keep their subject.
+        assert_eq!(nested.subject, None);
+        assert!(reply.created_at >= before);
+        assert!(nested.created_at >= reply.created_at);
+
+        let thread = stored(&service, thread.id).await;
+        assert_eq!(thread.reply_count, 1);
+        assert_eq!(thread.descendant_count, 2);
+        assert_eq!(thread.last_reply_at, Some(nested.created_at));
+
+        let reply = stored(&service, reply.id).await;
+        assert_eq!(reply.reply_count, 1);
+        assert_eq!(reply.descendant_count, 1);
+        assert_eq!(reply.last_reply_at, Some(nested.created_at));
+    }
+
+    #[tokio::test]
+    async fn deleted_replies_stop_counting() {
+        let service = service().await;
+        let thread = service.create(new_post("thread", None)).await.unwrap();
+        let reply = service
+            .create(new_post("reply", Some(thread.id)))
+            .await
+            .unwrap();
+        let nested = service
+            .create(NewPost {
+                password: Some(String::from("password")),
+                ..new_post("nested", Some(reply.id))
+            })
+            .await
+            .unwrap();
+
+        service
+            .delete(nested.id, String::from("password"), client("192.0.2.1"))
+            .await
+            .unwrap();
+
+        let thread = stored(&service, thread.id).await;
+        assert_eq!(thread.reply_count, 1);
+        assert_eq!(thread.descendant_count, 1);
+        assert_eq!(thread.last_reply_at, Some(reply.created_at));
+
+        // The tombstone keeps its place, but no longer bumps anything.
+        let reply = stored(&service, reply.id).await;
+        assert_eq!(reply.reply_count, 1);
+        assert_eq!(reply.descendant_count, 0);
+        assert_eq!(reply.last_reply_at, None);
+
+        // Purging a post that's already deleted doesn't take it off twice.
+        service.purge(nested.id).await.unwrap();
+        service.purge(reply.id).await.unwrap();
+
+        let thread = stored(&service, thread.id).await;
+        assert_eq!(thread.reply_count, 1);
+        assert_eq!(thread.descendant_count, 0);
+        assert_eq!(thread.last_reply_at, None);
+    }
+
+    #[tokio::test]
+    async fn rate_limits_each_client() {
+        let service = service().await;
+
+        let first = service
+            .create(from("192.0.2.1", new_post("first", None)))
+            .await;
+        let second = service
+            .create(from("192.0.2.1", new_post("second", None)))
+            .await;
+        let other = service
+            .create(from("192.0.2.2", new_post("other", None)))
+            .await;
+
+        assert!(first.is_ok());
+        assert!(matches!(second, Err(PostError::RateLimited)));
+        assert!(other.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rate_limits_posts_made_at_the_same_time() {
+        let service = service().await;
+
+        let (first, second) = tokio::join!(
+            service.create(from("192.0.2.1", new_post("first", None))),
+            service.create(from("192.0.2.1", new_post("second", None))),
+        );
+
+        assert!(first.is_ok());
+        assert!(matches!(second, Err(PostError::RateLimited)));
+    }
+
+    #[tokio::test]
+    async fn rate_limit_ends_after_the_cooldown() {
+        let service = service_with(Config {
+            post_cooldown: Duration::from_millis(50),
+            ..config()
+        })
+        .await;
+
+        service
+            .create(from("192.0.2.1", new_post("first", None)))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let second = service
+            .create(from("192.0.2.1", new_post("second", None)))
+            .await;
+
+        assert!(second.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejected_posts_dont_start_the_cooldown() {
+        let service = service().await;
+        let thread = service
+            .create(NewPost {
+                password: Some(String::from("password")),
+                ..new_post("thread", None)
+            })
+            .await
+            .unwrap();
+        service
+            .delete(thread.id, String::from("password"), client("192.0.2.9"))
+            .await
+            .unwrap();
+
+        let reply = service
+            .create(from("192.0.2.1", new_post("reply", Some(thread.id))))
+            .await;
+        let empty = service.create(from("192.0.2.1", new_post("", None))).await;
+        let next = service
+            .create(from("192.0.2.1", new_post("next", None)))
+            .await;
+
+        assert!(matches!(reply, Err(PostError::Deleted)));
+        assert!(matches!(empty, Err(PostError::Invalid(_))));
+        assert!(next.is_ok());
+    }
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Recorder {
+        fn record(&self, event: String) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    impl PostHook for Recorder {
+        fn created(&self, post: &post::Model) {
+            self.record(format!("created {}", post.content));
+        }
+
+        fn rejected(&self, reason: Rejection) {
+            self.record(format!("rejected {}", reason.label()));
+        }
+
+        fn edited(&self, post: &post::Model) {
+            self.record(format!("edited {}", post.content));
+        }
+
+        fn deleted(&self, post: &post::Model) {
+            self.record(format!("deleted {}", post.id));
+        }
+    }
+
+    #[tokio::test]
+    async fn tells_hooks_what_happened() {
+        let recorder = Arc::new(Recorder::default());
+        let service = service().await.with_hook(recorder.clone());
+
+        let thread = service
+            .create(NewPost {
+                password: Some(String::from("password")),
+                ..new_post("thread", None)
+            })
+            .await
+            .unwrap();
+        service.create(new_post(" ", None)).await.unwrap_err();
+        service
+            .edit(
+                thread.id,
+                String::from("password"),
+                "edited",
+                client("192.0.2.1"),
+            )
+            .await
+            .unwrap();
+        service
+            .delete(thread.id, String::from("password"), client("192.0.2.1"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            [
+                String::from("created thread"),
+                String::from("rejected empty_content"),
+                String::from("edited edited"),
+                format!("deleted {}", thread.id),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn edits_drop_the_signature() {
+        let service = service().await;
+        let thread = service
+            .create(NewPost {
+                password: Some(String::from("password")),
+                ..new_post("thread", None)
+            })
+            .await
+            .unwrap();
+
+        let mut signed: post::ActiveModel = thread.into();
+        signed.hash = ActiveValue::Set(Some(vec![1; 32]));
+        signed.signature = ActiveValue::Set(Some(vec![2; 64]));
+        let signed = signed.update(&service.db).await.unwrap();
+
+        let edited = service
+            .edit(signed.id, String::from("password"), "edited", client("192.0.2.1"))
+            .await
+            .unwrap();
+
+        assert_eq!(edited.content, "edited");
+        assert_eq!(edited.hash, None);
+        assert_eq!(edited.signature, None);
+    }
+
+    #[tokio::test]
+    async fn rate_limits_registering_identities() {
+        let service = service().await;
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = Base64UrlUnpadded::encode_string(key.verifying_key().as_bytes());
+
+        // Keys that aren't usable don't count.
+        let unusable = service
+            .register_identity("not a key", client("192.0.2.1"))
+            .await;
+        assert!(matches!(unusable, Ok(None)));
+
+        let first = service
+            .register_identity(&public_key, client("192.0.2.1"))
+            .await;
+        let second = service
+            .register_identity(&public_key, client("192.0.2.1"))
+            .await;
+        let post = service
+            .create(from("192.0.2.1", new_post("thread", None)))
+            .await;
+
+        assert!(matches!(first, Ok(Some(_))));
+        assert!(matches!(second, Err(PostError::RateLimited)));
+        assert!(matches!(post, Err(PostError::RateLimited)));
+    }
+
+    #[tokio::test]
+    async fn throttles_wrong_passwords() {
+        let service = service().await;
+        let thread = service
+            .create(NewPost {
+                password: Some(String::from("password")),
+                ..new_post("thread", None)
+            })
+            .await
+            .unwrap();
+        let attempt = |password: &str, address| {
+            service.edit(thread.id, password.to_owned(), "edited", client(address))
+        };
+
+        for _ in 0..MAX_PASSWORD_ATTEMPTS {
+            let wrong = attempt("wrong", "192.0.2.1").await;
+            assert!(matches!(wrong, Err(PostError::WrongPassword)));
+        }
+
+        // Even the right password isn't checked anymore.
+        let right = attempt("password", "192.0.2.1").await;
+        assert!(matches!(right, Err(PostError::TooManyAttempts)));
+
+        let other = attempt("password", "192.0.2.2").await;
+        assert_eq!(other.unwrap().content, "edited");
+    }
+}
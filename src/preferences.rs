@@ -0,0 +1,193 @@
+//! Per-browser settings, kept in cookies so nobody needs an account.
+
+use std::convert::Infallible;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use chrono_tz::Tz;
+
+use crate::{i18n::Locale, routes::replies::View};
+
+/// The color scheme pages are shown in.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Theme {
+    /// Whichever the browser prefers.
+    #[default]
+    System,
+    Light,
+    Dark,
+    HighContrast,
+}
+
+impl Theme {
+    pub const ALL: [Self; 4] = [Self::System, Self::Light, Self::Dark, Self::HighContrast];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::Light => "light",
+            Self::Dark => "dark",
+            Self::HighContrast => "high-contrast",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|theme| theme.as_str() == value)
+    }
+
+    /// The id of its name in the message catalogs.
+    pub fn message_id(self) -> &'static str {
+        match self {
+            Self::System => "theme-system",
+            Self::Light => "theme-light",
+            Self::Dark => "theme-dark",
+            Self::HighContrast => "theme-high-contrast",
+        }
+    }
+}
+
+/// How the current browser wants pages shown and forms filled in.
+#[derive(Clone)]
+pub struct Preferences {
+    /// Filled into the name field of every form, as `name#secret`.
+    pub poster: String,
+    pub theme: Theme,
+    /// `None` shows pages in the language the browser asks for.
+    pub language: Option<Locale>,
+    /// The best match for the browser's `Accept-Language`.
+    pub detected_locale: Locale,
+    /// `None` shows times in the timezone the browser reports, or else in UTC.
+    pub timezone: Option<Tz>,
+    /// The browser's own timezone, as reported by `static/time.js`.
+    pub detected_timezone: Option<Tz>,
+    /// How replies are laid out, unless a link asks for a specific view.
+    pub view: View,
+    /// Posts with at most this many replies have them loaded as soon as they scroll into view;
+    /// posts with more get a button instead.
+    pub lazy_threshold: i32,
+    pub hide_images: bool,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            poster: String::new(),
+            theme: Theme::default(),
+            language: None,
+            detected_locale: Locale::default(),
+            timezone: None,
+            detected_timezone: None,
+            view: View::default(),
+            lazy_threshold: Self::DEFAULT_LAZY_THRESHOLD,
+            hide_images: false,
+        }
+    }
+}
+
+impl Preferences {
+    pub const DEFAULT_LAZY_THRESHOLD: i32 = 4;
+
+    const POSTER: &str = "pref_poster";
+    const THEME: &str = "pref_theme";
+    const LANGUAGE: &str = "pref_language";
+    const TIMEZONE: &str = "pref_timezone";
+    /// Set by `static/time.js`, rather than on the preferences page.
+    const DETECTED_TIMEZONE: &str = "detected_timezone";
+    /// Named before there were other preferences.
+    const VIEW: &str = "replies_view";
+    const LAZY_THRESHOLD: &str = "pref_lazy_threshold";
+    const HIDE_IMAGES: &str = "pref_hide_images";
+
+    pub fn from_cookies(jar: &CookieJar) -> Self {
+        let get = |name| jar.get(name).map(|cookie| cookie.value());
+
+        Self {
+            poster: get(Self::POSTER).unwrap_or_default().to_owned(),
+            theme: get(Self::THEME).and_then(Theme::parse).unwrap_or_default(),
+            language: get(Self::LANGUAGE).and_then(Locale::parse),
+            detected_locale: Locale::default(),
+            timezone: get(Self::TIMEZONE).and_then(|timezone| timezone.parse().ok()),
+            detected_timezone: get(Self::DETECTED_TIMEZONE)
+                .and_then(|timezone| timezone.parse().ok()),
+            view: get(Self::VIEW).and_then(View::parse).unwrap_or_default(),
+            lazy_threshold: get(Self::LAZY_THRESHOLD)
+                .and_then(|threshold| threshold.parse().ok())
+                .unwrap_or(Self::DEFAULT_LAZY_THRESHOLD),
+            hide_images: get(Self::HIDE_IMAGES) == Some("true"),
+        }
+    }
+
+    /// The language pages are shown in.
+    pub fn locale(&self) -> Locale {
+        self.language.unwrap_or(self.detected_locale)
+    }
+
+    /// The timezone times are shown in.
+    pub fn display_timezone(&self) -> Tz {
+        self.timezone.or(self.detected_timezone).unwrap_or(Tz::UTC)
+    }
+
+    /// Remembers every preference in `jar`.
+    pub fn save(&self, jar: CookieJar) -> CookieJar {
+        let language = self.language.map(|language| language.as_str().to_owned());
+        let timezone = self.timezone.map(|timezone| timezone.name().to_owned());
+
+        // It holds the tripcode secret, which mustn't ever be sent in the clear. Browsers still
+        // keep secure cookies from `http://localhost`.
+        let mut poster = cookie(Self::POSTER, self.poster.clone());
+        poster.set_secure(true);
+
+        jar.add(poster)
+            .add(cookie(Self::THEME, self.theme.as_str().to_owned()))
+            .add(cookie(Self::LANGUAGE, language.unwrap_or_default()))
+            .add(cookie(Self::TIMEZONE, timezone.unwrap_or_default()))
+            .add(cookie(Self::VIEW, self.view.as_str().to_owned()))
+            .add(cookie(
+                Self::LAZY_THRESHOLD,
+                self.lazy_threshold.to_string(),
+            ))
+            .add(cookie(Self::HIDE_IMAGES, self.hide_images.to_string()))
+    }
+
+    /// Remembers only the theme, which is also picked from the header of every page.
+    pub fn save_theme(&self, jar: CookieJar) -> CookieJar {
+        jar.add(cookie(Self::THEME, self.theme.as_str().to_owned()))
+    }
+
+    /// Remembers only the replies view, which is also picked from the replies page.
+    pub fn save_view(&self, jar: CookieJar) -> CookieJar {
+        jar.add(cookie(Self::VIEW, self.view.as_str().to_owned()))
+    }
+}
+
+fn cookie(name: &'static str, value: String) -> Cookie<'static> {
+    Cookie::build(name, value)
+        .path("/")
+        .permanent()
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .finish()
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Preferences {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let jar = CookieJar::from_request_parts(parts, state).await?;
+
+        let accept_language = parts
+            .headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok());
+
+        Ok(Self {
+            detected_locale: accept_language.map(Locale::negotiate).unwrap_or_default(),
+            ..Self::from_cookies(&jar)
+        })
+    }
+}
@@ -0,0 +1,317 @@
+//! Statistics about a poster, for their profile page.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbBackend, DbErr};
+
+use crate::entities::{post, prelude::*};
+
+/// How many weeks back the activity histogram goes.
+pub const ACTIVITY_WEEKS: usize = 12;
+
+/// How many of the threads a poster wrote the most in are shown.
+const ACTIVE_THREADS: u32 = 5;
+
+/// Everything a poster wrote under a name, and optionally a tripcode.
+#[derive(Clone, Copy)]
+pub struct PosterFilter<'a> {
+    pub name: &'a str,
+    /// `None` matches the name with any tripcode, or none.
+    pub hash: Option<&'a [u8]>,
+}
+
+impl PosterFilter<'_> {
+    /// Their posts that weren't deleted.
+    pub fn condition(self) -> Condition {
+        Condition::all()
+            .add(post::Column::Name.eq(self.name))
+            .add(post::Column::DeletedAt.is_null())
+            .add_option(self.hash.map(|hash| post::Column::Hash.eq(hash.to_vec())))
+    }
+}
+
+/// What a poster has been up to.
+pub struct Profile {
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+    pub posts: u64,
+    /// Posts that started a thread.
+    pub threads: u64,
+    /// The threads they wrote the most in, with how many of their posts each has.
+    pub active_threads: Vec<(post::Model, i64)>,
+    /// How many posts they made each week, oldest first, ending with the current week.
+    pub activity: [u64; ACTIVITY_WEEKS],
+}
+
+impl Profile {
+    pub async fn load(db: &DatabaseConnection, poster: PosterFilter<'_>) -> Result<Self, DbErr> {
+        let posts = Post::find().filter(poster.condition());
+
+        // Ids go up in the order posts were made.
+        let first_seen = posts
+            .clone()
+            .select_only()
+            .column(post::Column::CreatedAt)
+            .order_by_asc(post::Column::Id)
+            .into_tuple::<DateTime<Utc>>()
+            .one(db)
+            .await?;
+
+        let last_seen = posts
+            .clone()
+            .select_only()
+            .column(post::Column::CreatedAt)
+            .order_by_desc(post::Column::Id)
+            .into_tuple::<DateTime<Utc>>()
+            .one(db)
+            .await?;
+
+        let threads = posts
+            .clone()
+            .filter(post::Column::ParentPostId.is_null())
+            .count(db)
+            .await?;
+
+        let now = Utc::now();
+        let recent = posts
+            .clone()
+            .select_only()
+            .column(post::Column::CreatedAt)
+            .filter(post::Column::CreatedAt.gt(now - Duration::weeks(ACTIVITY_WEEKS as i64)))
+            .into_tuple::<DateTime<Utc>>()
+            .all(db)
+            .await?;
+
+        let mut activity = [0; ACTIVITY_WEEKS];
+        for created_at in recent {
+            // Clocks drift, so a post can look like it's from the future.
+            let weeks_ago = (now - created_at)
+                .num_weeks()
+                .clamp(0, ACTIVITY_WEEKS as i64 - 1) as usize;
+            activity[ACTIVITY_WEEKS - 1 - weeks_ago] += 1;
+        }
+
+        Ok(Self {
+            first_seen,
+            last_seen,
+            posts: posts.count(db).await?,
+            threads,
+            active_threads: active_threads(db, poster).await?,
+            activity,
+        })
+    }
+}
+
+/// The threads `poster` wrote the most in, counting the post starting the thread.
+async fn active_threads(
+    db: &DatabaseConnection,
+    poster: PosterFilter<'_>,
+) -> Result<Vec<(post::Model, i64)>, DbErr> {
+    let mut counts = Vec::new();
+
+    for row in db
+        .query_all(thread_counts(db.get_database_backend(), poster))
+        .await?
+    {
+        counts.push((
+            row.try_get::<i32>("", "id")?,
+            row.try_get::<i64>("", "posts")?,
+        ));
+    }
+
+    let mut threads: HashMap<i32, post::Model> = Post::find()
+        .filter(post::Column::Id.is_in(counts.iter().map(|&(id, _)| id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|thread| (thread.id, thread))
+        .collect();
+
+    Ok(counts
+        .into_iter()
+        .filter_map(|(id, count)| Some((threads.remove(&id)?, count)))
+        .collect())
+}
+
+/// Builds a recursive query walking up from each of `poster`'s posts to the post starting
+/// its thread, then counting how many of their posts each thread has.
+fn thread_counts(backend: DbBackend, poster: PosterFilter<'_>) -> Statement {
+    let placeholder = |n: usize| match backend {
+        DbBackend::Postgres => format!("${n}"),
+        DbBackend::MySql | DbBackend::Sqlite => String::from("?"),
+    };
+
+    let (hash_filter, values) = match poster.hash {
+        Some(hash) => (
+            format!("AND hash = {}", placeholder(2)),
+            vec![poster.name.into(), hash.to_vec().into()],
+        ),
+        None => (String::new(), vec![poster.name.into()]),
+    };
+
+    let sql = format!(
+        "WITH RECURSIVE chain (id, parent_post_id) AS (
+            SELECT id, parent_post_id FROM post
+            WHERE name = {name} {hash_filter} AND deleted_at IS NULL
+            UNION ALL
+            SELECT post.id, post.parent_post_id
+            FROM post JOIN chain ON post.id = chain.parent_post_id
+        )
+        SELECT id, COUNT(*) AS posts FROM chain
+        WHERE parent_post_id IS NULL
+        GROUP BY id
+        ORDER BY posts DESC, id DESC
+        LIMIT {ACTIVE_THREADS}",
+        name = placeholder(1),
+    );
+
+    Statement::from_sql_and_values(backend, sql, values)
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::sea_query::Expr;
+
+    use super::*;
+    use crate::database;
+
+    async fn insert(
+        db: &DatabaseConnection,
+        name: &str,
+        hash: Option<&[u8]>,
+        parent_post_id: Option<i32>,
+        created_at: DateTime<Utc>,
+    ) -> post::Model {
+        let post = post::ActiveModel {
+            content: ActiveValue::Set(String::from("content")),
+            name: ActiveValue::Set(name.to_owned()),
+            hash: ActiveValue::Set(hash.map(<[u8]>::to_vec)),
+            parent_post_id: ActiveValue::Set(parent_post_id),
+            created_at: ActiveValue::Set(created_at),
+            ..Default::default()
+        };
+
+        Post::insert(post).exec_with_returning(db).await.unwrap()
+    }
+
+    fn counts(profile: &Profile) -> Vec<(i32, i64)> {
+        profile
+            .active_threads
+            .iter()
+            .map(|(thread, count)| (thread.id, *count))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn sums_up_a_posters_activity() {
+        let db = database::connect_test().await;
+        let now = Utc::now();
+        let (tripcode, other_tripcode) = (&[1][..], &[2][..]);
+
+        // Too long ago for the histogram.
+        let thread = insert(
+            &db,
+            "alice",
+            Some(tripcode),
+            None,
+            now - Duration::weeks(20),
+        )
+        .await;
+        let reply = insert(
+            &db,
+            "alice",
+            Some(tripcode),
+            Some(thread.id),
+            now - Duration::days(22),
+        )
+        .await;
+        let other_thread = insert(&db, "bob", None, None, now - Duration::days(5)).await;
+        insert(
+            &db,
+            "alice",
+            Some(tripcode),
+            Some(other_thread.id),
+            now - Duration::days(2),
+        )
+        .await;
+        let last = insert(
+            &db,
+            "alice",
+            Some(tripcode),
+            Some(reply.id),
+            now - Duration::days(1),
+        )
+        .await;
+        let deleted = insert(&db, "alice", Some(tripcode), None, now - Duration::days(1)).await;
+        Post::update_many()
+            .col_expr(post::Column::DeletedAt, Expr::value(now))
+            .filter(post::Column::Id.eq(deleted.id))
+            .exec(&db)
+            .await
+            .unwrap();
+        let impostor = insert(&db, "alice", Some(other_tripcode), None, now).await;
+
+        let profile = Profile::load(
+            &db,
+            PosterFilter {
+                name: "alice",
+                hash: Some(tripcode),
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(profile.first_seen, Some(thread.created_at));
+        assert_eq!(profile.last_seen, Some(last.created_at));
+        assert_eq!(profile.posts, 4);
+        assert_eq!(profile.threads, 1);
+        assert_eq!(counts(&profile), [(thread.id, 3), (other_thread.id, 1)]);
+
+        let mut activity = [0; ACTIVITY_WEEKS];
+        activity[ACTIVITY_WEEKS - 4] = 1;
+        activity[ACTIVITY_WEEKS - 1] = 2;
+        assert_eq!(profile.activity, activity);
+
+        // Without a tripcode, the name is all that counts.
+        let profile = Profile::load(
+            &db,
+            PosterFilter {
+                name: "alice",
+                hash: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(profile.last_seen, Some(impostor.created_at));
+        assert_eq!(profile.posts, 5);
+        assert_eq!(profile.threads, 2);
+        assert_eq!(
+            counts(&profile),
+            [(thread.id, 3), (impostor.id, 1), (other_thread.id, 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn has_nothing_on_unknown_posters() {
+        let db = database::connect_test().await;
+
+        let profile = Profile::load(
+            &db,
+            PosterFilter {
+                name: "nobody",
+                hash: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(profile.first_seen, None);
+        assert_eq!(profile.last_seen, None);
+        assert_eq!(profile.posts, 0);
+        assert_eq!(profile.threads, 0);
+        assert!(profile.active_threads.is_empty());
+        assert_eq!(profile.activity, [0; ACTIVITY_WEEKS]);
+    }
+}
@@ -1,32 +1,106 @@
 use std::fmt::Display;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Utc};
+use chrono_tz::Tz;
+use fluent::fluent_args;
 
-pub struct Relative(pub DateTime<Utc>);
+use crate::i18n::Locale;
+
+/// How long ago, or how long from now, a time is, worded in `locale`. Times further than
+/// 3 weeks away are shown as a date in `timezone` instead.
+pub struct Relative {
+    pub time: DateTime<Utc>,
+    pub timezone: Tz,
+    pub locale: Locale,
+}
+
+impl Relative {
+    /// The date and time, in `timezone`, worded in `locale` the way `static/time.js` does.
+    pub fn absolute(&self) -> String {
+        let time = self.time.with_timezone(&self.timezone);
+
+        // chrono only knows month names in English.
+        let month = self
+            .locale
+            .text_with("time-month", &fluent_args!["month" => time.month()]);
+        let format = self
+            .locale
+            .text("time-date-format")
+            .replace("%b", &month.replace('%', "%%"));
+
+        time.format(&format).to_string()
+    }
+}
 
 impl Display for Relative {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let now = Utc::now();
-        let diff = now - self.0;
+        let diff = Utc::now() - self.time;
+        // Clocks drift, and posts can be scheduled: times ahead of now read "in ...".
+        let future = diff < chrono::Duration::zero();
+        let diff = if future { -diff } else { diff };
 
-        let (unit, value) = if diff.num_weeks() > 3 {
-            // If it's been more than 3 weeks, just show the date.
-            return write!(f, "{}", self.0.format("%Y-%m-%d %H:%M:%S %Z"));
+        let (message, value) = if diff.num_weeks() > 3 {
+            // If it's more than 3 weeks away, just show the date.
+            return write!(f, "{}", self.absolute());
         } else if diff.num_weeks() > 0 {
-            ("week", diff.num_weeks()) 
+            ("time-weeks", diff.num_weeks())
         } else if diff.num_days() > 0 {
-            ("day", diff.num_days())
+            ("time-days", diff.num_days())
         } else if diff.num_hours() > 0 {
-            ("hour", diff.num_hours())
+            ("time-hours", diff.num_hours())
         } else if diff.num_minutes() > 0 {
-            ("minute", diff.num_minutes())
+            ("time-minutes", diff.num_minutes())
         } else if diff.num_seconds() < 2 {
-            // If it's been less than 2 seconds, just say "just now".
-            return write!(f, "just now");
+            // If it's less than 2 seconds away, just say "just now".
+            return write!(f, "{}", self.locale.text("time-just-now"));
         } else {
-            ("second", diff.num_seconds())
+            ("time-seconds", diff.num_seconds())
+        };
+
+        let duration = self
+            .locale
+            .text_with(message, &fluent_args!["count" => value]);
+        let message = if future { "time-in" } else { "time-ago" };
+
+        let text = self
+            .locale
+            .text_with(message, &fluent_args!["duration" => duration]);
+
+        write!(f, "{text}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::i18n;
+
+    #[test]
+    fn words_dates_in_the_locale_and_timezone() {
+        i18n::load().unwrap();
+        let time = Utc.with_ymd_and_hms(2023, 9, 1, 13, 4, 5).unwrap();
+        let absolute = |timezone, locale| {
+            Relative {
+                time,
+                timezone,
+                locale,
+            }
+            .absolute()
         };
 
-        write!(f, "{} {}{} ago", value, unit, if value == 1 { "" } else { "s" })
+        assert_eq!(
+            absolute(Tz::UTC, Locale::English),
+            "Sep 1, 2023, 1:04:05 PM"
+        );
+        assert_eq!(
+            absolute(Tz::Europe__Paris, Locale::French),
+            "1 sept. 2023, 15:04:05"
+        );
+        assert_eq!(
+            absolute(Tz::America__New_York, Locale::English),
+            "Sep 1, 2023, 9:04:05 AM"
+        );
     }
 }
@@ -1,19 +1,55 @@
 use std::fmt::Display;
 
 use axum_extra::routing::TypedPath;
+use fluent::fluent_args;
 use maud::{html, Markup};
 
-use crate::entities::post;
+use crate::{
+    entities::post,
+    preferences::{Preferences, Theme},
+    reply_tree::ReplyTree,
+    validation::FieldError,
+};
+
+pub fn layout(prefs: &Preferences, title: &str, body: Markup) -> Markup {
+    layout_with_description(prefs, title, None, body)
+}
+
+/// Like [`layout`], with a summary of the page for link previews.
+pub fn layout_with_description(
+    prefs: &Preferences,
+    title: &str,
+    description: Option<&str>,
+    body: Markup,
+) -> Markup {
+    // Without a theme attribute, the browser's preferred color scheme applies.
+    let theme = match prefs.theme {
+        Theme::System => None,
+        theme => Some(theme.as_str()),
+    };
+    let locale = prefs.locale();
 
-pub fn layout(title: &str, body: Markup) -> Markup {
     html! {
         (maud::DOCTYPE)
-        html {
+        html
+            lang=(locale.as_str())
+            data-theme=[theme]
+            data-timezone=[prefs.timezone.map(|timezone| timezone.name())]
+        {
             head {
                 title { (title) }
+                meta property="og:site_name" content="clovers";
+                meta property="og:title" content=(title);
+                @if let Some(description) = description {
+                    meta name="description" content=(description);
+                    meta property="og:description" content=(description);
+                }
                 script src="https://unpkg.com/htmx.org@1.9.4" { }
                 script src="https://unpkg.com/alpinejs" defer { }
                 script src="https://cdn.jsdelivr.net/npm/@unocss/runtime/attributify.global.js" { }
+                script src="/static/time.js" defer { }
+                // Before Alpine starts, so forms can tell whether there's an identity to sign with.
+                script src="/static/identity.js" { }
                 style {"
                     [un-cloak] { display: none; }
 
@@ -27,14 +63,33 @@ pub fn layout(title: &str, body: Markup) -> Markup {
                         transform: translateY(-2rem);
                         opacity: 0;
                     }
+
+                    [data-empty]:empty::after { content: attr(data-empty); }
                 "}
+                script {(maud::PreEscaped(r#"
+                    // Rejected submissions come back as 422s, carrying error messages for the form.
+                    document.addEventListener("htmx:beforeSwap", (event) => {
+                        if (event.detail.xhr.status === 422) {
+                            event.detail.shouldSwap = true;
+                            event.detail.isError = false;
+                        }
+                    });
+                "#))}
                 link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/@unocss/reset/tailwind.min.css";
+                link rel="stylesheet" href="/static/theme.css";
                 //link rel="stylesheet" href="https://unpkg.com/modern-normalize";
                 //link rel="stylesheet" href="/static/style.css";
             }
-            body bg="#f0f0f0" hx-boost="true" un-cloak {
-                header bg="white" z="10" sticky top="0" p="8" shadow="md" {
-                    h1 font="size-8 bold" { (link("/", "clovers")) }
+            body bg="$neutral-color" text="$text-color" hx-boost="true" un-cloak {
+                header bg="$container-color" z="10" sticky top="0" p="8" shadow="md" {
+                    nav flex="~ row items-baseline" gap="6" {
+                        h1 font="size-8 bold" { (link("/", "clovers")) }
+                        (link(crate::routes::catalog::CatalogPath::PATH, locale.text("nav-catalog")))
+                        (link(crate::routes::archive::ArchivePath::PATH, locale.text("nav-archive")))
+                        (link(crate::routes::user::UsersPath::PATH, locale.text("nav-users")))
+                        (link(crate::routes::preferences::PreferencesPath::PATH, locale.text("nav-preferences")))
+                        (theme_switcher(prefs))
+                    }
                 }
                 main mx="a" p="x-8 y-12" max-w="4xl" flex="~ col" gap="8" {
                     (body)
@@ -44,46 +99,203 @@ pub fn layout(title: &str, body: Markup) -> Markup {
     }
 }
 
+/// Switches the theme of the page right away, and remembers it for the next ones.
+fn theme_switcher(prefs: &Preferences) -> Markup {
+    use crate::routes::preferences::ThemePath;
+
+    let locale = prefs.locale();
+
+    html! {
+        select
+            name="theme"
+            m="l-auto"
+            bg="$container-color"
+            aria-label=(locale.text("theme"))
+            hx-post=(ThemePath)
+            hx-trigger="change"
+            hx-swap="none"
+            x-data
+            x-on:change="$event.target.value === 'system'
+                ? delete document.documentElement.dataset.theme
+                : document.documentElement.dataset.theme = $event.target.value"
+        {
+            @for theme in Theme::ALL {
+                option value=(theme.as_str()) selected[theme == prefs.theme] {
+                    (locale.text(theme.message_id()))
+                }
+            }
+        }
+    }
+}
+
 pub fn link(href: impl Display, text: impl maud::Render) -> Markup {
     html! {
-        a text="#038b25" hover:underline href=(href) { (text) }
+        a text="$link-color" hover:underline href=(href) { (text) }
+    }
+}
+
+/// A post's content, with the links in it made clickable. Links to images show the image,
+/// unless the reader hides images: it's then never fetched from wherever it's hosted.
+pub fn post_content(prefs: &Preferences, content: &str) -> Markup {
+    html! {
+        pre font-sans {
+            @for piece in content.split_inclusive(char::is_whitespace) {
+                @let url = piece.trim_end_matches(char::is_whitespace);
+                @let space = &piece[url.len()..];
+                @if !is_url(url) {
+                    (piece)
+                } @else if is_image(url) && !prefs.hide_images {
+                    // Posted links lead off the site, so they aren't boosted.
+                    a href=(url) rel="nofollow noopener noreferrer" hx-boost="false" {
+                        img block max-w="full" max-h="96" src=(url) alt=(url) loading="lazy" referrerpolicy="no-referrer";
+                    }
+                    (space)
+                } @else {
+                    a text="$link-color" hover:underline href=(url) rel="nofollow noopener noreferrer" hx-boost="false" { (url) }
+                    (space)
+                }
+            }
+        }
     }
 }
 
-pub fn relative_time(time: chrono::DateTime<chrono::Utc>) -> Markup {
+fn is_url(word: &str) -> bool {
+    ["http://", "https://"]
+        .iter()
+        .any(|scheme| word.len() > scheme.len() && word.starts_with(scheme))
+}
+
+/// Whether the path of `url` ends in the extension of an image format browsers show.
+fn is_image(url: &str) -> bool {
+    const EXTENSIONS: [&str; 6] = ["avif", "gif", "jpeg", "jpg", "png", "webp"];
+
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+
+    path.rsplit_once('.')
+        .is_some_and(|(_, extension)| EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+/// How long ago `time` was, in the reader's timezone. `static/time.js` keeps it up to date,
+/// and in the reader's language.
+pub fn relative_time(prefs: &Preferences, time: chrono::DateTime<chrono::Utc>) -> Markup {
+    let relative = crate::relative_time::Relative {
+        time,
+        timezone: prefs.display_timezone(),
+        locale: prefs.locale(),
+    };
+
     html! {
-        time datetime=(time) title=(time) {
-            (crate::relative_time::Relative(time))
+        time datetime=(time.to_rfc3339()) title=(relative.absolute()) data-relative {
+            (relative)
         }
     }
 }
 
-pub fn post_form_body() -> &'static Markup {
-    use std::sync::OnceLock;
+/// The fields of a post or reply form; `form` tells its error messages apart from other forms'.
+///
+/// Only posts starting a thread get a subject. Posters who made an identity in their
+/// preferences can sign with it.
+pub fn post_form_body(
+    prefs: &Preferences,
+    form: &str,
+    fields: &[crate::validation::Field],
+) -> Markup {
+    use crate::validation::Field;
 
-    static CELL: OnceLock<Markup> = OnceLock::new();
+    let locale = prefs.locale();
 
-    CELL.get_or_init(|| html! {
+    html! {
+        (error_slot(&form_error_id(form, None), &[]))
         label flex="~ col" {
-            span { "Name (optional)" }
-            input name="poster" placeholder="Anonymous" autocomplete="off";
+            span { (locale.text("name-optional")) }
+            input name="poster" value=(prefs.poster) placeholder=(locale.text("anonymous")) autocomplete="off";
+            (error_slot(&form_error_id(form, Some(Field::Name)), &[]))
+        }
+        div x-data="{ fingerprint: identity.fingerprint() }" {
+            template x-if="fingerprint" {
+                label flex="~ row items-center" gap="2" {
+                    input type="checkbox" checked data-sign data-sign-error=(locale.text("identity-unavailable"));
+                    span { (locale.text("sign-with-identity")) }
+                    span font-mono text="0.9rem $trip-color" truncate x-text="'🔑' + fingerprint" { }
+                }
+            }
+        }
+        @if fields.contains(&Field::Subject) {
+            label flex="~ col" {
+                span { (locale.text("subject-optional")) }
+                input name="subject" autocomplete="off";
+                (error_slot(&form_error_id(form, Some(Field::Subject)), &[]))
+            }
         }
         label flex="~ col" {
-            span { "Content" }
-            textarea resize="none" rows="10" name="content" placeholder="What's on your mind?" { }
+            span { (locale.text("content")) }
+            textarea resize="none" rows="10" name="content" placeholder=(locale.text("content-placeholder")) { }
+            (error_slot(&form_error_id(form, Some(Field::Content)), &[]))
         }
+        (password_input(prefs))
         div flex="~ row justify-end" gap="4" {
-            button hover:underline rounded type="button" x-on:click="open = false" { "Cancel" }
+            button hover:underline rounded type="button" x-on:click="open = false" { (locale.text("cancel")) }
             button p="x-4 y-1"
                 rounded
-                bg="#038b25"
-                text="white"
+                bg="$primary-color"
+                text="$on-primary-color"
                 scale="100 hover:110 active:90"
                 transition="transform-100"
                 ease-in
-            { "Post" }
+            { (locale.text("post")) }
+        }
+    }
+}
+
+/// Out-of-band replacements for every error slot of `form`, so stale messages are cleared too.
+///
+/// `general` is the id of a message about the whole submission.
+pub fn form_errors(
+    prefs: &Preferences,
+    form: &str,
+    fields: &[crate::validation::Field],
+    general: Option<&str>,
+    errors: &[FieldError],
+) -> Markup {
+    let locale = prefs.locale();
+    let general = general.map(|id| locale.text(id));
+
+    html! {
+        (error_slot_oob(&form_error_id(form, None), &general.into_iter().collect::<Vec<_>>()))
+        @for &field in fields {
+            (error_slot_oob(
+                &form_error_id(form, Some(field)),
+                &errors
+                    .iter()
+                    .filter(|error| error.field == field)
+                    .map(|error| error.message(locale))
+                    .collect::<Vec<_>>(),
+            ))
+        }
+    }
+}
+
+fn form_error_id(form: &str, field: Option<crate::validation::Field>) -> String {
+    match field {
+        Some(field) => format!("errors-{form}-{}", field.as_str()),
+        None => format!("errors-{form}"),
+    }
+}
+
+fn error_slot(id: &str, messages: &[&str]) -> Markup {
+    html! {
+        span id=(id) text="$error-color" empty:hidden {
+            @for message in messages { (message) " " }
+        }
+    }
+}
+
+fn error_slot_oob(id: &str, messages: &[impl maud::Render]) -> Markup {
+    html! {
+        span id=(id) text="$error-color" empty:hidden hx-swap-oob="true" {
+            @for message in messages { (message) " " }
         }
-    })
+    }
 }
 
 pub fn post_list(children: Markup) -> Markup {
@@ -94,84 +306,382 @@ pub fn post_list(children: Markup) -> Markup {
     }
 }
 
-pub fn posts(posts: Vec<post::Model>) -> Markup {
+/// Links to the pages before and after `page`, out of `pages`; `path` gives the link to a page.
+pub fn pagination(
+    prefs: &Preferences,
+    page: u64,
+    pages: u64,
+    path: impl Fn(u64) -> String,
+) -> Markup {
+    let locale = prefs.locale();
+
+    html! {
+        @if pages > 1 {
+            nav flex="~ row items-baseline" gap="4" {
+                @if page > 1 {
+                    (link(path(page - 1), locale.text("previous-page")))
+                }
+                span text="$muted-color" {
+                    (locale.text_with("page-of", &fluent_args!["page" => page, "pages" => pages]))
+                }
+                @if page < pages {
+                    (link(path(page + 1), locale.text("next-page")))
+                }
+            }
+        }
+    }
+}
+
+pub fn posts(prefs: &Preferences, posts: Vec<post::Model>) -> Markup {
     post_list(
         html! {
             @for post in posts {
-                li { (self::post(post)) }
+                li { (self::post(prefs, post)) }
             }
         }
     )
 }
 
-pub fn post(post: post::Model) -> Markup {
+pub fn post(prefs: &Preferences, post: post::Model) -> Markup {
     use crate::routes::replies::RepliesPath;
 
+    let locale = prefs.locale();
     let replies_path = RepliesPath { id: post.id };
+    let actions = post_actions(prefs, &post);
 
     html! {
-        article p="8" bg="white" shadow="md" flex="~ col" gap="4" {
-            span { "Posted " (relative_time(post.created_at)) }
-            (poster_link(post.name, post.hash.as_deref()))
-            pre font-sans { (post.content) }
-            (link(replies_path, "View Replies"))
+        article p="8" bg="$container-color" shadow="md" flex="~ col" gap="4" {
+            @if post.deleted_at.is_some() {
+                (tombstone(prefs, &post))
+            } @else {
+                @if let Some(subject) = &post.subject {
+                    h2 font="size-6 bold" { (subject) }
+                }
+                span {
+                    (locale.text("posted")) " " (relative_time(prefs, post.created_at))
+                    (edited_marker(prefs, &post))
+                    @if let Some(archived_at) = post.archived_at {
+                        ", " (locale.text("archived")) " " (relative_time(prefs, archived_at))
+                    }
+                }
+                (poster_link(post.name, post.hash.as_deref(), post.signature.is_some()))
+                (post_content(prefs, &post.content))
+                (actions)
+            }
+            footer {
+                (link(replies_path, locale.text("view-replies")))
+                @if let Some(last_reply_at) = post.last_reply_at {
+                    " ("
+                    (locale.text_with("reply-count", &fluent_args!["count" => post.descendant_count]))
+                    ", " (locale.text("last-reply")) " " (relative_time(prefs, last_reply_at)) ")"
+                }
+            }
+        }
+    }
+}
+
+/// A compact summary of a thread, for the catalog.
+pub fn catalog_card(prefs: &Preferences, post: post::Model) -> Markup {
+    use crate::routes::replies::RepliesPath;
+
+    const EXCERPT_LENGTH: usize = 150;
+
+    let locale = prefs.locale();
+    let replies_path = RepliesPath { id: post.id };
+    let bumped_at = post.last_reply_at.unwrap_or(post.created_at);
+
+    let mut excerpt: String = post.content.chars().take(EXCERPT_LENGTH).collect();
+    if excerpt.len() < post.content.len() {
+        excerpt.push('…');
+    }
+
+    html! {
+        a href=(replies_path) {
+            article h="full" p="4" bg="$container-color" rounded shadow="md hover:lg" flex="~ col" gap="2" {
+                @if let Some(subject) = &post.subject {
+                    h3 font-bold truncate { (subject) }
+                }
+                p break-words { (excerpt) }
+                footer text="sm $muted-color" m="t-auto" {
+                    (locale.text_with("catalog-reply-count", &fluent_args!["count" => post.descendant_count]))
+                    " · " (locale.text("bumped")) " " (relative_time(prefs, bumped_at))
+                }
+            }
         }
     }
 }
 
-pub fn reply(post: post::Model) -> Markup {
-    use crate::routes::replies::{RepliesPath, RepliesLazyPath};
+/// Renders a reply, along with whichever of its own replies are in `tree`.
+pub fn reply(prefs: &Preferences, post: post::Model, tree: &mut ReplyTree) -> Markup {
+    use crate::routes::replies::{RepliesLazyPath, View};
 
     let id = post.id;
-    let replies_path = RepliesPath { id };
     let replies_lazy_path = RepliesLazyPath { id };
+    let children = tree.take_children(id);
 
     html! {
-        article p="4" bg="white" rounded shadow="md" flex="~ col" gap="4" {
+        (reply_article(prefs, &post, View::Nested))
+        @if let Some(children) = children {
+            (nested_replies(prefs, id, children, tree))
+        } @else if post.reply_count > 0 {
+            div hidden hx-trigger="revealed" hx-get=(replies_lazy_path) hx-swap="outerHTML" { }
+        } @else {
+            // Nothing to load, but replies made to this one still need somewhere to go.
+            (nested_replies(prefs, id, Vec::new(), tree))
+        }
+    }
+}
+
+/// Renders a reply on its own, pointing back at the post it replies to.
+pub fn flat_reply(prefs: &Preferences, post: post::Model) -> Markup {
+    reply_article(prefs, &post, crate::routes::replies::View::Flat)
+}
+
+/// A reply without the replies to it. In the flat view, it points back at its parent.
+pub fn reply_article(
+    prefs: &Preferences,
+    post: &post::Model,
+    view: crate::routes::replies::View,
+) -> Markup {
+    html! {
+        @if post.deleted_at.is_some() {
+            article p="4" bg="$container-color" rounded shadow="md" {
+                (tombstone(prefs, post))
+            }
+        } @else {
+            (live_reply_article(prefs, post, view))
+        }
+    }
+}
+
+fn live_reply_article(
+    prefs: &Preferences,
+    post: &post::Model,
+    view: crate::routes::replies::View,
+) -> Markup {
+    use crate::routes::replies::{RepliesPath, View};
+
+    let locale = prefs.locale();
+    let id = post.id;
+    let replies_path = RepliesPath { id };
+
+    html! {
+        article p="4" bg="$container-color" rounded shadow="md" flex="~ col" gap="4" {
             header {
-                (poster_link(post.name, post.hash.as_deref()))
-                span { " Posted " (link(replies_path, relative_time(post.created_at))) }
+                (poster_link(post.name.clone(), post.hash.as_deref(), post.signature.is_some()))
+                span { " " (locale.text("posted")) " " (link(replies_path, relative_time(prefs, post.created_at))) (edited_marker(prefs, post)) }
+                @if let (View::Flat, Some(parent_id)) = (view, post.parent_post_id) {
+                    span { " " (locale.text("in-reply-to")) " " (link(RepliesPath { id: parent_id }, html! { ">>" (parent_id) })) }
+                }
             }
-            pre font-sans { (post.content) }
-            footer x-data="{ open: false }" {
-                button x-show="!open" x-on:click="open = true" { "Reply" }
-                (reply_form_template(id))
+            (post_content(prefs, &post.content))
+            (post_actions(prefs, post))
+            @if post.archived_at.is_none() {
+                footer x-data="{ open: false }" {
+                    button x-show="!open" x-on:click="open = true" { (locale.text("reply")) }
+                    (reply_form_template(prefs, id, view))
+                }
             }
         }
-        div hidden hx-trigger="revealed" hx-get=(replies_lazy_path) hx-swap="outerHTML" { }
     }
 }
 
-pub fn reply_form_template(post_id: i32) -> Markup {
-    use crate::routes::replies::RepliesPath;
+/// " (edited)", linking to the post's edit history, if it was ever edited.
+fn edited_marker(prefs: &Preferences, post: &post::Model) -> Markup {
+    use crate::routes::replies::HistoryPath;
+
+    html! {
+        @if let Some(edited_at) = post.edited_at {
+            " "
+            a text="$muted-color" hover:underline href=(HistoryPath { id: post.id }) title=(edited_at.with_timezone(&prefs.display_timezone())) {
+                (prefs.locale().text("edited"))
+            }
+        }
+    }
+}
+
+/// The lines `newer` removed from and added to `older`.
+pub fn revision_diff(older: &str, newer: &str) -> Markup {
+    html! {
+        pre font-sans {
+            @for line in diff::lines(older, newer) {
+                @match line {
+                    diff::Result::Left(line) => { del block bg="$removed-color" no-underline { "- " (line) } }
+                    diff::Result::Both(line, _) => { span block { "  " (line) } }
+                    diff::Result::Right(line) => { ins block bg="$added-color" no-underline { "+ " (line) } }
+                }
+            }
+        }
+    }
+}
+
+/// Stands in for a deleted post, keeping its place among the replies.
+fn tombstone(prefs: &Preferences, post: &post::Model) -> Markup {
+    use crate::post_service::Deletion;
+
+    let removed = post.deleted_by.as_deref() == Some(Deletion::Takedown.as_str());
+
+    html! {
+        span text="$muted-color" italic {
+            (prefs.locale().text(if removed { "removed" } else { "deleted" }))
+            @if let Some(deleted_at) = post.deleted_at {
+                " " (relative_time(prefs, deleted_at))
+            }
+        }
+    }
+}
+
+/// "Delete" and "Edit" for the author of `post`, who proves it with the post's password.
+fn post_actions(prefs: &Preferences, post: &post::Model) -> Markup {
+    use crate::{
+        routes::replies::{DeletePath, EditPath},
+        validation::Field,
+    };
+
+    let locale = prefs.locale();
+    let id = post.id;
+    let delete_form = format!("delete-{id}");
+    let edit_form = format!("edit-{id}");
+
+    html! {
+        @if post.password_hash.is_some() && post.archived_at.is_none() {
+            div x-data="{ action: null }" flex="~ col" gap="2" text="sm" {
+                div x-show="action === null" flex="~ row" gap="4" {
+                    button type="button" hover:underline x-on:click="action = 'delete'" { (locale.text("delete")) }
+                    button type="button" hover:underline x-on:click="action = 'edit'" { (locale.text("edit")) }
+                }
+                template x-if="action === 'delete'" {
+                    form
+                        flex="~ col"
+                        gap="2"
+                        hx-post=(DeletePath { id })
+                        hx-target="closest article"
+                        hx-swap="outerHTML"
+                        x-init="$nextTick(() => htmx.process($el))"
+                    {
+                        (error_slot(&form_error_id(&delete_form, None), &[]))
+                        (password_input(prefs))
+                        (action_buttons(prefs, &locale.text("delete")))
+                    }
+                }
+                template x-if="action === 'edit'" {
+                    form
+                        flex="~ col"
+                        gap="2"
+                        hx-post=(EditPath { id })
+                        hx-target="closest article"
+                        hx-swap="outerHTML"
+                        x-init="$nextTick(() => htmx.process($el))"
+                    {
+                        (error_slot(&form_error_id(&edit_form, None), &[]))
+                        textarea resize="none" rows="6" name="content" { (post.content) }
+                        (error_slot(&form_error_id(&edit_form, Some(Field::Content)), &[]))
+                        (password_input(prefs))
+                        (action_buttons(prefs, &locale.text("save")))
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn password_input(prefs: &Preferences) -> Markup {
+    let locale = prefs.locale();
+
+    html! {
+        label flex="~ col" {
+            span { (locale.text("password-optional")) }
+            input
+                type="password"
+                name="password"
+                autocomplete="off"
+                placeholder=(locale.text("password-placeholder"));
+        }
+    }
+}
+
+fn action_buttons(prefs: &Preferences, submit: &str) -> Markup {
+    html! {
+        div flex="~ row justify-end" gap="4" {
+            button hover:underline type="button" x-on:click="action = null" { (prefs.locale().text("cancel")) }
+            button p="x-4 y-1" rounded bg="$primary-color" text="$on-primary-color" { (submit) }
+        }
+    }
+}
+
+pub fn nested_replies(
+    prefs: &Preferences,
+    post_id: i32,
+    replies: Vec<post::Model>,
+    tree: &mut ReplyTree,
+) -> Markup {
+    html! {
+        ul #{"replies-" (post_id)}
+            .replies
+            empty:hidden
+            ml="3"
+            pl="3"
+            b="l-2 $primary-color"
+            flex="~ col self-stretch"
+            gap="4"
+            role="list"
+        {
+            @for reply in replies {
+                li flex="~ col" gap="4" {
+                    (self::reply(prefs, reply, tree))
+                }
+            }
+        }
+    }
+}
+
+pub fn reply_form_template(
+    prefs: &Preferences,
+    post_id: i32,
+    view: crate::routes::replies::View,
+) -> Markup {
+    use crate::{
+        routes::replies::{RepliesPath, View},
+        validation::Field,
+    };
 
     let replies_path = RepliesPath { id: post_id };
 
+    // Nested replies go on top of their parent's replies, flat ones at the end of the thread.
+    let (target, swap) = match view {
+        View::Nested => (format!("#replies-{post_id}"), "afterbegin"),
+        View::Flat => (String::from("#flat-replies"), "beforeend"),
+    };
+
     html! {
         template x-if="open" {
             form
                 flex="~ col"
                 gap="4"
                 hx-post=(replies_path)
-                hx-target={"#replies-" (post_id)}
-                hx-swap="afterbegin"
-                x-init="$nextTick(() => htmx.process($el))"
-                x-on:submit="$nextTick(() => open = false)"
-            { (post_form_body()) }
+                data-parent-post-id=(post_id)
+                hx-target=(target)
+                hx-swap=(swap)
+                // Only close once the submission went through, so validation errors can be shown.
+                // maud takes a single colon in attribute names, so listen from `x-init`.
+                x-init="$nextTick(() => htmx.process($el)); $el.addEventListener('htmx:afterRequest', (event) => event.detail.xhr.status < 300 && (open = false))"
+            { (post_form_body(prefs, &format!("reply-{post_id}"), Field::REPLY)) }
         }
     }
 }
 
-pub fn poster_link(name: String, bytes: Option<&[u8]>) -> Markup {
+/// `signed` if `bytes` is the fingerprint of an identity rather than a tripcode hash.
+pub fn poster_link(name: String, bytes: Option<&[u8]>, signed: bool) -> Markup {
     use crate::routes::user::{UserPath, UserQuery};
     use base64ct::Encoding;
 
     let serialized_hash = bytes.map(base64ct::Base64UrlUnpadded::encode_string);
 
-    let rendered_poster = poster(&name, serialized_hash.as_deref());
+    let rendered_poster = poster(&name, serialized_hash.as_deref(), signed);
 
     let user_path = UserPath { name }.with_query_params(UserQuery {
         hash: serialized_hash,
+        page: None,
     });
 
     html! {
@@ -181,15 +691,57 @@ pub fn poster_link(name: String, bytes: Option<&[u8]>) -> Markup {
     }
 }
 
-pub fn poster(name: &str, hash: Option<&str>) -> Markup {
+/// A name and its tripcode; the fingerprint of an identity shows with a key instead of `#`.
+pub fn poster(name: &str, hash: Option<&str>, signed: bool) -> Markup {
+    let marker = if signed { "🔑" } else { "#" };
+
     html! {
         span.poster font-bold {
             span {
                 (name)
             }
             @if let Some(tripcode) = hash {
-                " (" span inline-block align-btm max-w="20 hover:none" truncate text="0.9rem #038b25" font-mono { "#" (tripcode) } ")"
+                " (" span inline-block align-btm max-w="20 hover:none" truncate text="0.9rem $trip-color" font-mono { (marker) (tripcode) } ")"
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_linked_images_unless_hidden() {
+        let content = "look https://example.com/cat.JPG?size=large\nhttps://example.com/page";
+        let shown = post_content(&Preferences::default(), content).into_string();
+        let hidden = post_content(
+            &Preferences {
+                hide_images: true,
+                ..Preferences::default()
+            },
+            content,
+        )
+        .into_string();
+
+        assert!(shown.contains("<img"));
+        assert!(shown.contains(r#"src="https://example.com/cat.JPG?size=large""#));
+        assert!(!hidden.contains("<img"));
+        assert!(hidden.contains(r#">https://example.com/cat.JPG?size=large</a>"#));
+
+        for rendered in [shown, hidden] {
+            assert!(rendered.contains(r#">https://example.com/page</a>"#));
+            assert!(rendered.contains("look "));
+        }
+    }
+
+    #[test]
+    fn leaves_text_that_isnt_a_link_alone() {
+        let rendered = post_content(&Preferences::default(), "see http:// or <b>x.png</b>");
+
+        assert_eq!(
+            rendered.into_string(),
+            r#"<pre font-sans>see http:// or &lt;b&gt;x.png&lt;/b&gt;</pre>"#
+        );
+    }
+}
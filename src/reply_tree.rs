@@ -0,0 +1,110 @@
+use std::collections::{HashMap, HashSet};
+
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, Statement};
+
+use crate::entities::{post, prelude::*};
+
+/// The replies beneath a post, down to a fixed depth, fetched in a single query.
+#[derive(Default)]
+pub struct ReplyTree {
+    children: HashMap<i32, Vec<post::Model>>,
+    /// Posts whose replies were fetched, even if they turned out to have none.
+    expanded: HashSet<i32>,
+}
+
+impl ReplyTree {
+    /// Loads every reply beneath `root_id` that's at most `max_depth` levels down.
+    pub async fn load(
+        db: &DatabaseConnection,
+        root_id: i32,
+        max_depth: u32,
+    ) -> Result<Self, DbErr> {
+        let replies = Post::find()
+            .from_raw_sql(descendants(
+                db.get_database_backend(),
+                root_id,
+                Some(max_depth),
+            ))
+            .all(db)
+            .await?;
+
+        let mut tree = Self::default();
+        let mut depths = HashMap::from([(root_id, 0)]);
+        tree.expanded.insert(root_id);
+
+        // Replies always have a higher id than their parent, so a reply's parent has
+        // already been seen by the time the reply comes up.
+        for reply in replies {
+            let Some(parent_id) = reply.parent_post_id else {
+                continue;
+            };
+
+            let depth = depths.get(&parent_id).map_or(1, |depth| depth + 1);
+
+            depths.insert(reply.id, depth);
+            if depth < max_depth {
+                tree.expanded.insert(reply.id);
+            }
+
+            tree.children.entry(parent_id).or_default().push(reply);
+        }
+
+        Ok(tree)
+    }
+
+    /// Takes the replies to `post_id`, or `None` if they were too deep to be fetched.
+    pub fn take_children(&mut self, post_id: i32) -> Option<Vec<post::Model>> {
+        self.expanded
+            .contains(&post_id)
+            .then(|| self.children.remove(&post_id).unwrap_or_default())
+    }
+}
+
+/// Loads every reply beneath `root_id`, however deep, oldest first.
+pub async fn load_descendants<C: ConnectionTrait>(
+    db: &C,
+    root_id: i32,
+) -> Result<Vec<post::Model>, DbErr> {
+    let mut replies = Post::find()
+        .from_raw_sql(descendants(db.get_database_backend(), root_id, None))
+        .all(db)
+        .await?;
+
+    replies.sort_by_key(|reply| (reply.created_at, reply.id));
+
+    Ok(replies)
+}
+
+/// Builds a recursive query selecting the replies beneath `root_id`, down to `max_depth`
+/// levels if given.
+///
+/// Rows are ordered by id, which is also the order they were posted in.
+fn descendants(backend: DbBackend, root_id: i32, max_depth: Option<u32>) -> Statement {
+    let placeholder = |n: usize| match backend {
+        DbBackend::Postgres => format!("${n}"),
+        DbBackend::MySql | DbBackend::Sqlite => String::from("?"),
+    };
+
+    let (depth_filter, values) = match max_depth {
+        Some(max_depth) => (
+            format!("WHERE tree.depth < {}", placeholder(2)),
+            vec![root_id.into(), max_depth.into()],
+        ),
+        None => (String::new(), vec![root_id.into()]),
+    };
+
+    let sql = format!(
+        "WITH RECURSIVE tree (id, depth) AS (
+            SELECT id, 1 FROM post WHERE parent_post_id = {root}
+            UNION ALL
+            SELECT post.id, tree.depth + 1
+            FROM post JOIN tree ON post.parent_post_id = tree.id
+            {depth_filter}
+        )
+        SELECT post.* FROM post JOIN tree ON post.id = tree.id
+        ORDER BY post.id",
+        root = placeholder(1),
+    );
+
+    Statement::from_sql_and_values(backend, sql, values)
+}
@@ -0,0 +1,44 @@
+use axum::extract::State;
+use axum_extra::routing::TypedPath;
+use maud::{html, Markup};
+use sea_orm::{entity::*, query::*};
+use serde::Deserialize;
+
+use crate::{
+    entities::{post, prelude::*},
+    preferences::Preferences,
+    render, AppResult, AppState,
+};
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/archive")]
+pub struct ArchivePath;
+
+pub async fn get_archive(
+    _: ArchivePath,
+    State(state): State<AppState>,
+    prefs: Preferences,
+) -> AppResult<Markup> {
+    let threads = Post::find()
+        .filter(post::Column::ParentPostId.is_null())
+        .filter(post::Column::ArchivedAt.is_not_null())
+        .filter(post::Column::DeletedAt.is_null())
+        .order_by_desc(post::Column::ArchivedAt)
+        .order_by_desc(post::Column::Id)
+        .all(&state.db)
+        .await?;
+
+    let locale = prefs.locale();
+
+    Ok(render::layout(
+        &prefs,
+        &format!("clovers :: {}", locale.text("title-archive")),
+        html! {
+            section flex="~ col items-start" gap="4" {
+                h2 font="size-5 bold" { (locale.text("archive")) }
+                p { (locale.text("archive-description")) }
+                (render::posts(&prefs, threads))
+            }
+        },
+    ))
+}
@@ -0,0 +1,93 @@
+use axum::extract::{Query, State};
+use axum_extra::routing::TypedPath;
+use maud::{html, Markup};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::post,
+    preferences::Preferences,
+    render,
+    routes::posts::{self, ThreadOrder},
+    AppResult, AppState,
+};
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/catalog")]
+pub struct CatalogPath;
+
+#[derive(Serialize, Deserialize)]
+pub struct CatalogQuery {
+    #[serde(default)]
+    pub sort: ThreadOrder,
+}
+
+pub async fn get_catalog(
+    _: CatalogPath,
+    State(state): State<AppState>,
+    Query(query): Query<CatalogQuery>,
+    prefs: Preferences,
+) -> AppResult<Markup> {
+    let threads = posts::threads(query.sort).all(&state.db).await?;
+
+    let locale = prefs.locale();
+
+    let sort_options = [
+        (ThreadOrder::Bumped, locale.text("sort-bumped")),
+        (ThreadOrder::Created, locale.text("sort-created")),
+        (ThreadOrder::Replies, locale.text("sort-replies")),
+    ];
+
+    Ok(render::layout(
+        &prefs,
+        &format!("clovers :: {}", locale.text("title-catalog")),
+        html! {
+            section flex="~ col items-start" gap="4" x-data="{ filter: '' }" {
+                h2 font="size-5 bold" { (locale.text("catalog")) }
+                nav flex="~ row wrap" gap="2" {
+                    span { (locale.text("sort-by")) }
+                    @for (sort, label) in sort_options {
+                        @if sort == query.sort {
+                            span font-bold { (label) }
+                        } @else {
+                            (render::link(
+                                CatalogPath.with_query_params(CatalogQuery { sort }),
+                                label,
+                            ))
+                        }
+                    }
+                }
+                input
+                    type="search"
+                    placeholder=(locale.text("filter-threads"))
+                    autocomplete="off"
+                    x-model="filter"
+                    p="x-2 y-1"
+                    rounded
+                    b="1 $border-color";
+                ul
+                    w="full"
+                    grid="~ cols-1 sm:cols-2 md:cols-3"
+                    gap="4"
+                    role="list"
+                    data-empty=(locale.text("no-threads"))
+                {
+                    @for thread in threads {
+                        li
+                            data-search=(catalog_search_text(&thread))
+                            x-show="$el.dataset.search.includes(filter.toLowerCase())"
+                        {
+                            (render::catalog_card(&prefs, thread))
+                        }
+                    }
+                }
+            }
+        },
+    ))
+}
+
+/// The lowercased text the client-side filter matches against.
+fn catalog_search_text(thread: &post::Model) -> String {
+    let subject = thread.subject.as_deref().unwrap_or_default();
+
+    format!("{subject}\n{}\n{}", thread.name, thread.content).to_lowercase()
+}
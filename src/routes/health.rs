@@ -0,0 +1,112 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::{extract::State, http::StatusCode, Json};
+use axum_extra::routing::TypedPath;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/healthz")]
+pub struct HealthPath;
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/readyz")]
+pub struct ReadyPath;
+
+/// Numbers probe files, so concurrent probes don't trip over each other's.
+static PROBES: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Serialize)]
+pub struct Health {
+    status: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct Readiness {
+    status: &'static str,
+    checks: Checks,
+}
+
+#[derive(Serialize)]
+pub struct Checks {
+    database: Check,
+    migrations: Check,
+    disk: Check,
+}
+
+#[derive(Serialize)]
+pub struct Check {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl<E: std::fmt::Display> From<Result<(), E>> for Check {
+    fn from(result: Result<(), E>) -> Self {
+        Self {
+            ok: result.is_ok(),
+            error: result.err().map(|err| err.to_string()),
+        }
+    }
+}
+
+/// Liveness: the process is up and serving requests.
+pub async fn healthz(_: HealthPath) -> Json<Health> {
+    Json(Health { status: "ok" })
+}
+
+/// Readiness: the database is reachable, fully migrated, and the disk can be written to.
+pub async fn readyz(_: ReadyPath, State(state): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    use migration::MigratorTrait;
+
+    let database = Check::from(state.db.ping().await);
+
+    let migrations = Check::from(
+        migration::Migrator::get_pending_migrations(&state.db)
+            .await
+            .map_err(|err| err.to_string())
+            .and_then(|pending| match pending.len() {
+                0 => Ok(()),
+                count => Err(format!("{count} pending migration(s)")),
+            }),
+    );
+
+    // Each probe gets a file of its own, in the directory the database is written to.
+    let probe = state.config.probe_dir.join(format!(
+        ".readyz-{}-{}",
+        std::process::id(),
+        PROBES.fetch_add(1, Ordering::Relaxed),
+    ));
+    let disk = Check::from(
+        async {
+            tokio::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&probe)
+                .await?;
+            tokio::fs::remove_file(&probe).await
+        }
+        .await,
+    );
+
+    let ready = database.ok && migrations.ok && disk.ok;
+
+    let (status, label) = if ready {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+
+    (
+        status,
+        Json(Readiness {
+            status: label,
+            checks: Checks {
+                database,
+                migrations,
+                disk,
+            },
+        }),
+    )
+}
@@ -0,0 +1,46 @@
+use axum::{extract::State, http::StatusCode, Form};
+use axum_extra::routing::TypedPath;
+use base64ct::Encoding;
+use serde::Deserialize;
+
+use crate::{client_ip::ClientIp, post_service::PostError, AppResult, AppState};
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/identities")]
+pub struct IdentitiesPath;
+
+/// Request body for the `/identities` route.
+#[derive(Deserialize)]
+pub struct RegisterIdentity {
+    /// Ed25519 public key, base64url.
+    public_key: String,
+}
+
+/// Registers an identity made by `static/identity.js`, answering with its fingerprint, which
+/// signed posts then give to name it. Registering takes the client's turn to post.
+pub async fn register_identity(
+    _: IdentitiesPath,
+    State(state): State<AppState>,
+    ClientIp(client): ClientIp,
+    Form(form): Form<RegisterIdentity>,
+) -> AppResult<String> {
+    match state
+        .posts
+        .register_identity(&form.public_key, client)
+        .await
+    {
+        Ok(Some(fingerprint)) => Ok(base64ct::Base64UrlUnpadded::encode_string(&fingerprint)),
+        Ok(None) => Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            String::from("Not an Ed25519 public key"),
+        )
+            .into()),
+        Err(PostError::Database(err)) => Err(err.into()),
+        // Short of the database, only the rate limit turns a registration away.
+        Err(_) => Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            String::from("Too many requests, try again later"),
+        )
+            .into()),
+    }
+}
@@ -1,37 +1,57 @@
+pub mod archive;
+pub mod catalog;
+pub mod health;
+pub mod identity;
 pub mod posts;
+pub mod preferences;
 pub mod replies;
 pub mod user;
 
-use axum::extract::State;
-use axum_extra::routing::TypedPath;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_extra::{
+    extract::cookie::{Cookie, CookieJar, SameSite},
+    routing::TypedPath,
+};
 use maud::{html, Markup};
-use sea_orm::{entity::*, query::*};
+use sea_orm::query::*;
 use serde::Deserialize;
 
 use crate::{
-    entities::{post, prelude::*},
-    render, AppResult, AppState,
+    password,
+    post_service::PostError,
+    preferences::Preferences,
+    render,
+    validation::Field,
+    AppResult, AppState,
 };
 
 #[derive(TypedPath, Deserialize)]
 #[typed_path("/")]
 pub struct RootPath;
 
-pub async fn root(_: RootPath, State(state): State<AppState>) -> AppResult<Markup> {
-    let posts = Post::find()
-        .filter(post::Column::ParentPostId.is_null())
-        .order_by_desc(post::Column::Id)
+pub async fn root(
+    _: RootPath,
+    State(state): State<AppState>,
+    prefs: Preferences,
+) -> AppResult<Markup> {
+    let posts = posts::threads(posts::ThreadOrder::Created)
         .limit(3)
         .all(&state.db)
         .await?;
 
+    let locale = prefs.locale();
     let posts_path = posts::PostsPath::PATH;
 
     Ok(render::layout(
+        &prefs,
         "clovers",
         html! {
-            section p="8" bg="white" rounded shadow="md" x-data="{ open: false }" {
-                button x-on:click="open = true" x-show="!open" { "Make a Post" }
+            section p="8" bg="$container-color" rounded shadow="md" x-data="{ open: false }" {
+                button x-on:click="open = true" x-show="!open" { (locale.text("make-a-post")) }
                 template x-if="open" {
                     form
                         flex="~ col"
@@ -40,18 +60,100 @@ pub async fn root(_: RootPath, State(state): State<AppState>) -> AppResult<Marku
                         hx-target="#posts"
                         hx-select="#posts li"
                         hx-swap="afterbegin"
-                        x-init="$nextTick(() => htmx.process($el))"
-                        // This has to be done next tick because otherwise htmx won't execute the post request.
-                        // An alternative could be to use x-show and clear the form, but that would be more complicated.
-                        x-on:submit="$nextTick(() => open = false)"
-                    { (render::post_form_body()) }
+                        // Only close once the post went through, so validation errors can be shown.
+                        // maud takes a single colon in attribute names, so listen from `x-init`.
+                        x-init="$nextTick(() => htmx.process($el)); $el.addEventListener('htmx:afterRequest', (event) => event.detail.xhr.status < 300 && (open = false))"
+                    { (render::post_form_body(&prefs, "post", Field::POST)) }
                 }
             }
             section flex="~ col items-start" gap="4" {
-                h2 font="size-5 bold" { "Recent Posts" }
-                (render::posts(posts))
-                (render::link(posts_path, "View More"))
+                h2 font="size-5 bold" { (locale.text("recent-posts")) }
+                (render::posts(&prefs, posts))
+                (render::link(posts_path, locale.text("view-more")))
             }
         },
     ))
 }
+
+/// Cookie holding the random password of posts made from this browser without one.
+const PASSWORD_COOKIE: &str = "post_password";
+
+/// The password to make a post with: the one typed in, or else this browser's own, which is
+/// created on first use.
+fn post_password(jar: CookieJar, typed: &str) -> (CookieJar, String) {
+    if !typed.is_empty() {
+        return (jar, typed.to_owned());
+    }
+
+    if let Some(cookie) = jar.get(PASSWORD_COOKIE) {
+        let password = cookie.value().to_owned();
+        return (jar, password);
+    }
+
+    let password = password::token();
+    let cookie = Cookie::build(PASSWORD_COOKIE, password.clone())
+        .path("/")
+        .permanent()
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .finish();
+
+    (jar.add(cookie), password)
+}
+
+/// The password given to delete or edit a post: the one typed in, or else this browser's own.
+fn given_password(jar: &CookieJar, typed: &str) -> String {
+    if !typed.is_empty() {
+        return typed.to_owned();
+    }
+
+    jar.get(PASSWORD_COOKIE)
+        .map(|cookie| cookie.value().to_owned())
+        .unwrap_or_default()
+}
+
+/// Responds to a submission that didn't go through, showing why in the form `form`.
+fn rejected_submission(
+    prefs: &Preferences,
+    form: &str,
+    fields: &[Field],
+    err: PostError,
+) -> AppResult<Response> {
+    let general = match err {
+        PostError::Invalid(errors) => {
+            let errors = render::form_errors(prefs, form, fields, None, &errors);
+            return Ok(rejected(errors));
+        }
+        PostError::RateLimited => "error-rate-limited",
+        PostError::Archived => "error-archived",
+        PostError::Deleted => "error-deleted",
+        PostError::WrongPassword => "error-wrong-password",
+        PostError::TooManyAttempts => "error-too-many-attempts",
+        PostError::EditWindowClosed => "error-edit-window-closed",
+        PostError::UnknownIdentity => "error-unknown-identity",
+        PostError::BadSignature => "error-bad-signature",
+        PostError::NotFound => {
+            return Err((StatusCode::NOT_FOUND, String::from("Not Found")).into())
+        }
+        PostError::Database(err) => return Err(err.into()),
+    };
+
+    Ok(rejected(render::form_errors(
+        prefs,
+        form,
+        fields,
+        Some(general),
+        &[],
+    )))
+}
+
+/// A 422 carrying the error messages of a form.
+fn rejected(errors: Markup) -> Response {
+    // Only the out-of-band error messages get swapped in.
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        [("HX-Reswap", "none")],
+        errors,
+    )
+        .into_response()
+}
@@ -1,13 +1,26 @@
-use axum::{extract::State, Form};
-use axum_extra::routing::TypedPath;
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    Form,
+};
+use axum_extra::{extract::cookie::CookieJar, routing::TypedPath};
 use maud::{html, Markup};
-use sea_orm::{entity::*, query::*};
-use serde::Deserialize;
+use sea_orm::{
+    entity::*,
+    query::*,
+    sea_query::{Expr, Func, SimpleExpr},
+};
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    client_ip::ClientIp,
     entities::{post, prelude::*},
-    poster::Poster,
-    render, AppResult, AppState,
+    identity::Signed,
+    post_service::NewPost,
+    preferences::Preferences,
+    render,
+    validation::Field,
+    AppResult, AppState,
 };
 
 #[derive(TypedPath, Deserialize)]
@@ -19,43 +32,103 @@ pub struct PostsPath;
 pub struct MakePost {
     content: String,
     poster: String,
+    #[serde(default)]
+    subject: String,
+    #[serde(default)]
+    password: String,
+    /// Filled in by `static/identity.js` when signing with an identity.
+    #[serde(default)]
+    identity: String,
+    #[serde(default)]
+    signature: String,
+    #[serde(default)]
+    signed_at: String,
+}
+
+/// How a list of threads is ordered.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadOrder {
+    /// Newest thread first.
+    #[default]
+    Created,
+    /// Most recently replied to first.
+    Bumped,
+    /// Most replies first.
+    Replies,
 }
 
-pub async fn get_posts(_: PostsPath, State(state): State<AppState>) -> AppResult<Markup> {
-    let posts = Post::find()
-        .filter(post::Column::ParentPostId.is_null())
-        .order_by_desc(post::Column::Id)
-        .all(&state.db)
-        .await?;
+impl ThreadOrder {
+    /// Sorts `threads` in this order.
+    pub fn apply(self, threads: Select<Post>) -> Select<Post> {
+        match self {
+            Self::Created => threads.order_by_desc(post::Column::Id),
+            Self::Bumped => threads
+                .order_by_desc(SimpleExpr::from(Func::coalesce([
+                    Expr::col(post::Column::LastReplyAt).into(),
+                    Expr::col(post::Column::CreatedAt).into(),
+                ])))
+                .order_by_desc(post::Column::Id),
+            Self::Replies => threads
+                .order_by_desc(post::Column::DescendantCount)
+                .order_by_desc(post::Column::Id),
+        }
+    }
+}
+
+/// Every thread-starting post that isn't archived or deleted, in `order`.
+pub fn threads(order: ThreadOrder) -> Select<Post> {
+    order.apply(
+        Post::find()
+            .filter(post::Column::ParentPostId.is_null())
+            .filter(post::Column::ArchivedAt.is_null())
+            .filter(post::Column::DeletedAt.is_null()),
+    )
+}
+
+pub async fn get_posts(
+    _: PostsPath,
+    State(state): State<AppState>,
+    prefs: Preferences,
+) -> AppResult<Markup> {
+    let posts = threads(ThreadOrder::Created).all(&state.db).await?;
 
     Ok(render::layout(
-        "clovers :: posts",
-        render::posts(posts),
+        &prefs,
+        &format!("clovers :: {}", prefs.locale().text("title-posts")),
+        render::posts(&prefs, posts),
     ))
 }
 
 pub async fn make_post(
     _: PostsPath,
     State(state): State<AppState>,
+    ClientIp(client): ClientIp,
+    jar: CookieJar,
+    prefs: Preferences,
     Form(post): Form<MakePost>,
-) -> AppResult<Markup> {
-    if post.content.is_empty() {
-        return Ok(Markup::default());
-    }
+) -> AppResult<Response> {
+    let (jar, password) = super::post_password(jar, &post.password);
 
-    let Poster { name, hash } = post.poster.parse().expect("Infallible");
+    let result = state
+        .posts
+        .create(NewPost {
+            content: post.content,
+            poster: post.poster,
+            subject: Some(post.subject),
+            parent_post_id: None,
+            client: Some(client),
+            password: Some(password),
+            signed: Signed::from_form(post.identity, post.signature, post.signed_at),
+        })
+        .await;
 
-    let post = post::ActiveModel {
-        content: ActiveValue::Set(post.content),
-        name: ActiveValue::Set(name),
-        hash: ActiveValue::Set(hash),
-        created_at: ActiveValue::Set(chrono::Utc::now()),
-        ..Default::default()
+    let post = match result {
+        Ok(post) => post,
+        Err(err) => return super::rejected_submission(&prefs, "post", Field::POST, err),
     };
 
-    let post = Post::insert(post).exec_with_returning(&state.db).await?;
-
-    let rendered_post = render::post(post);
+    let rendered_post = render::post(&prefs, post);
 
-    Ok(render::post_list(html! { li.fade-in { (rendered_post) } }))
+    Ok((jar, render::post_list(html! { li.fade-in { (rendered_post) } })).into_response())
 }
@@ -0,0 +1,228 @@
+use axum::{http::StatusCode, Form};
+use axum_extra::{extract::cookie::CookieJar, routing::TypedPath};
+use fluent::fluent_args;
+use maud::{html, Markup};
+use serde::Deserialize;
+
+use crate::{
+    i18n::Locale,
+    preferences::{Preferences, Theme},
+    render,
+    routes::replies::View,
+};
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/preferences")]
+pub struct PreferencesPath;
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/preferences/theme")]
+pub struct ThemePath;
+
+/// Request body for the `/preferences/theme` route.
+#[derive(Deserialize)]
+pub struct SaveTheme {
+    theme: String,
+}
+
+/// Request body for the `/preferences` route.
+#[derive(Deserialize)]
+pub struct SavePreferences {
+    #[serde(default)]
+    poster: String,
+    theme: String,
+    #[serde(default)]
+    language: String,
+    #[serde(default)]
+    timezone: String,
+    view: String,
+    lazy_threshold: i32,
+    /// Checkboxes are only sent when checked.
+    hide_images: Option<String>,
+}
+
+pub async fn get_preferences(_: PreferencesPath, prefs: Preferences) -> Markup {
+    preferences_page(&prefs, false)
+}
+
+pub async fn save_preferences(
+    _: PreferencesPath,
+    jar: CookieJar,
+    current: Preferences,
+    Form(form): Form<SavePreferences>,
+) -> (CookieJar, Markup) {
+    let prefs = Preferences {
+        poster: form.poster,
+        theme: Theme::parse(&form.theme).unwrap_or_default(),
+        language: Locale::parse(&form.language),
+        detected_locale: current.detected_locale,
+        timezone: form.timezone.parse().ok(),
+        detected_timezone: current.detected_timezone,
+        view: View::parse(&form.view).unwrap_or_default(),
+        lazy_threshold: form.lazy_threshold.max(0),
+        hide_images: form.hide_images.is_some(),
+    };
+
+    (prefs.save(jar), preferences_page(&prefs, true))
+}
+
+/// Remembers the theme picked in the header. The page already switched to it by itself.
+pub async fn save_theme(
+    _: ThemePath,
+    jar: CookieJar,
+    mut prefs: Preferences,
+    Form(form): Form<SaveTheme>,
+) -> (CookieJar, StatusCode) {
+    prefs.theme = Theme::parse(&form.theme).unwrap_or_default();
+
+    (prefs.save_theme(jar), StatusCode::NO_CONTENT)
+}
+
+fn preferences_page(prefs: &Preferences, saved: bool) -> Markup {
+    let locale = prefs.locale();
+
+    let automatic_language = locale.text_with(
+        "language-automatic",
+        &fluent_args!["language" => prefs.detected_locale.name()],
+    );
+    let automatic_timezone = locale.text_with(
+        "timezone-automatic",
+        &fluent_args![
+            "timezone" => prefs.detected_timezone.map_or("UTC", |timezone| timezone.name())
+        ],
+    );
+
+    render::layout(
+        prefs,
+        &format!("clovers :: {}", locale.text("title-preferences")),
+        html! {
+            section p="8" bg="$container-color" rounded shadow="md" flex="~ col" gap="4" {
+                h2 font="size-5 bold" { (locale.text("preferences")) }
+                p { (locale.text("preferences-description")) }
+                @if saved {
+                    p text="$primary-color" { (locale.text("preferences-saved")) }
+                }
+                form method="post" action=(PreferencesPath) flex="~ col" gap="4" {
+                    label flex="~ col" {
+                        span { (locale.text("default-name")) }
+                        input
+                            name="poster"
+                            value=(prefs.poster)
+                            placeholder=(locale.text("anonymous"))
+                            autocomplete="off";
+                        span text="sm $muted-color" { (locale.text("default-name-help")) }
+                    }
+                    label flex="~ col" {
+                        span { (locale.text("theme")) }
+                        select name="theme" {
+                            @for theme in Theme::ALL {
+                                option value=(theme.as_str()) selected[theme == prefs.theme] {
+                                    (locale.text(theme.message_id()))
+                                }
+                            }
+                        }
+                    }
+                    label flex="~ col" {
+                        span { (locale.text("language")) }
+                        select name="language" {
+                            option value="" selected[prefs.language.is_none()] {
+                                (automatic_language)
+                            }
+                            @for language in Locale::ALL {
+                                option value=(language.as_str()) lang=(language.as_str()) selected[prefs.language == Some(language)] {
+                                    (language.name())
+                                }
+                            }
+                        }
+                    }
+                    label flex="~ col" {
+                        span { (locale.text("timezone")) }
+                        select name="timezone" {
+                            option value="" selected[prefs.timezone.is_none()] {
+                                (automatic_timezone)
+                            }
+                            @for timezone in chrono_tz::TZ_VARIANTS {
+                                option value=(timezone.name()) selected[prefs.timezone == Some(timezone)] {
+                                    (timezone.name())
+                                }
+                            }
+                        }
+                    }
+                    label flex="~ col" {
+                        span { (locale.text("replies-view")) }
+                        select name="view" {
+                            @for view in [View::Nested, View::Flat] {
+                                option value=(view.as_str()) selected[view == prefs.view] {
+                                    (locale.text(view.message_id()))
+                                }
+                            }
+                        }
+                    }
+                    label flex="~ col" {
+                        span { (locale.text("lazy-threshold")) }
+                        input type="number" name="lazy_threshold" min="0" value=(prefs.lazy_threshold);
+                        span text="sm $muted-color" { (locale.text("lazy-threshold-help")) }
+                    }
+                    label flex="~ col" {
+                        span flex="~ row items-center" gap="2" {
+                            input type="checkbox" name="hide_images" checked[prefs.hide_images];
+                            span { (locale.text("hide-images")) }
+                        }
+                        span text="sm $muted-color" { (locale.text("hide-images-help")) }
+                    }
+                    div flex="~ row justify-end" {
+                        button p="x-4 y-1" rounded bg="$primary-color" text="$on-primary-color" { (locale.text("save")) }
+                    }
+                }
+            }
+            (identity_section(prefs))
+        },
+    )
+}
+
+/// Makes or forgets this browser's identity, with `static/identity.js`. It lives in the
+/// browser, not in a cookie, since the private key must never be sent.
+fn identity_section(prefs: &Preferences) -> Markup {
+    let locale = prefs.locale();
+
+    html! {
+        section
+            p="8"
+            bg="$container-color"
+            rounded
+            shadow="md"
+            flex="~ col items-start"
+            gap="4"
+            x-data="{ fingerprint: identity.fingerprint(), failed: false }"
+        {
+            h2 font="size-5 bold" { (locale.text("identity")) }
+            p { (locale.text("identity-description")) }
+            template x-if="fingerprint" {
+                div flex="~ col items-start" gap="2" {
+                    span {
+                        (locale.text("identity-fingerprint")) " "
+                        span font-mono text="$trip-color" break-all x-text="'🔑' + fingerprint" { }
+                    }
+                    button
+                        hover:underline
+                        data-confirm=(locale.text("forget-identity-confirm"))
+                        x-on:click="confirm($el.dataset.confirm) && (identity.forget(), fingerprint = null)"
+                    { (locale.text("forget-identity")) }
+                }
+            }
+            template x-if="!fingerprint" {
+                div flex="~ col items-start" gap="2" {
+                    span text="$muted-color" { (locale.text("identity-none")) }
+                    button
+                        p="x-4 y-1"
+                        rounded
+                        bg="$primary-color"
+                        text="$on-primary-color"
+                        x-on:click="identity.create().then((created) => fingerprint = created, () => failed = true)"
+                    { (locale.text("create-identity")) }
+                }
+            }
+            p text="$error-color" x-show="failed" { (locale.text("identity-unavailable")) }
+        }
+    }
+}
@@ -1,12 +1,26 @@
-use axum::{extract::{State, Query}, http::StatusCode, Form};
-use axum_extra::routing::TypedPath;
+use axum::{
+    extract::{State, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Form,
+};
+use axum_extra::{
+    extract::cookie::CookieJar,
+    routing::TypedPath,
+};
+use fluent::fluent_args;
 use maud::{html, Markup};
 use sea_orm::{entity::*, query::*};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    entities::{post, prelude::*},
-    render, AppResult, AppState, poster::Poster,
+    client_ip::ClientIp,
+    entities::{post, post_revision, prelude::*},
+    identity::Signed,
+    post_service::NewPost,
+    preferences::Preferences,
+    validation::Field,
+    render, reply_tree::{self, ReplyTree}, AppResult, AppState,
 };
 
 #[derive(TypedPath, Deserialize)]
@@ -19,6 +33,42 @@ pub struct RepliesPath {
 pub struct RepliesQuery {
     #[serde(default)]
     pub nested: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view: Option<View>,
+}
+
+/// How the replies to a post are laid out.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum View {
+    /// Replies beneath the post they reply to.
+    #[default]
+    Nested,
+    /// Every reply in the thread, oldest first.
+    Flat,
+}
+
+impl View {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Nested => "nested",
+            Self::Flat => "flat",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [Self::Nested, Self::Flat]
+            .into_iter()
+            .find(|view| view.as_str() == value)
+    }
+
+    /// The id of its name in the message catalogs.
+    pub fn message_id(self) -> &'static str {
+        match self {
+            Self::Nested => "view-nested",
+            Self::Flat => "view-flat",
+        }
+    }
 }
 
 #[derive(TypedPath, Deserialize)]
@@ -32,65 +82,148 @@ pub struct RepliesLazyPath {
 pub struct MakeReply {
     content: String,
     poster: String,
+    #[serde(default)]
+    password: String,
+    /// Filled in by `static/identity.js` when signing with an identity.
+    #[serde(default)]
+    identity: String,
+    #[serde(default)]
+    signature: String,
+    #[serde(default)]
+    signed_at: String,
+}
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/replies/:id/delete")]
+pub struct DeletePath {
+    pub id: i32,
+}
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/replies/:id/edit")]
+pub struct EditPath {
+    pub id: i32,
+}
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/replies/:id/history")]
+pub struct HistoryPath {
+    pub id: i32,
+}
+
+/// Request body for the `/replies/:id/delete` route.
+#[derive(Deserialize)]
+pub struct DeletePost {
+    #[serde(default)]
+    password: String,
+}
+
+/// Request body for the `/replies/:id/edit` route.
+#[derive(Deserialize)]
+pub struct EditPost {
+    #[serde(default)]
+    password: String,
+    content: String,
 }
 
 pub async fn get_replies(
     RepliesPath { id }: RepliesPath,
     State(state): State<AppState>,
     Query(query): Query<RepliesQuery>,
-) -> AppResult<Markup> {
-    let replies = Post::find()
-        .filter(post::Column::ParentPostId.eq(id))
-        .order_by_asc(post::Column::Id)
-        .all(&state.db)
-        .await?;
+    jar: CookieJar,
+    mut prefs: Preferences,
+) -> AppResult<(CookieJar, Markup)> {
+    // Picking a view sticks for the next threads too.
+    let jar = match query.view {
+        Some(view) => {
+            prefs.view = view;
+            prefs.save_view(jar)
+        }
+        None => jar,
+    };
+
+    if prefs.view == View::Flat && !query.nested {
+        return Ok((jar, get_flat_replies(id, &state, &prefs).await?));
+    }
+
+    let mut tree = ReplyTree::load(&state.db, id, state.config.reply_depth).await?;
+    let replies = tree.take_children(id).unwrap_or_default();
 
     // Nested replies are rendered differently
     if query.nested {
-        return Ok(html! {
-            ul #{"replies-" (id)}
-                .replies
-                empty:hidden
-                ml="3"
-                pl="3"
-                b="l-2 #038b25"
-                flex="~ col self-stretch"
-                gap="4"
-                role="list"
-            {
-                @for reply in replies {
-                    li flex="~ col" gap="4" {
-                        (render::reply(reply))
+        return Ok((jar, render::nested_replies(&prefs, id, replies, &mut tree)));
+    }
+
+    let post = Post::find_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Not Found: {id}")))?;
+
+    let locale = prefs.locale();
+    let (title, description) = page_meta(&prefs, &post);
+
+    let page = render::layout_with_description(
+        &prefs,
+        &title,
+        Some(&description),
+        html! {
+            @let closed = post.archived_at.is_some() || post.deleted_at.is_some();
+            (render::post(&prefs, post))
+            (reply_section(&prefs, id, closed, View::Nested))
+            section flex="~ col items-start" gap="4" {
+                h2 font="size-5 bold" { (locale.text("replies")) }
+                (view_toggle(&prefs, id, View::Nested))
+                ul #{"replies-" (id)}
+                    data-empty=(locale.text("no-replies"))
+                    flex="~ col self-stretch"
+                    gap="4"
+                    role="list"
+                {
+                    @for reply in replies {
+                        li flex="~ col" gap="4" {
+                            (render::reply(&prefs, reply, &mut tree))
+                        }
                     }
                 }
             }
-        })
-    }
+        },
+    );
 
+    Ok((jar, page))
+}
+
+/// Renders every reply in the thread under `id` as one chronological list.
+async fn get_flat_replies(id: i32, state: &AppState, prefs: &Preferences) -> AppResult<Markup> {
     let post = Post::find_by_id(id)
         .one(&state.db)
         .await?
         .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Not Found: {id}")))?;
 
-    Ok(render::layout(
-        "clovers :: replies",
+    let replies = reply_tree::load_descendants(&state.db, id).await?;
+
+    let locale = prefs.locale();
+    let (title, description) = page_meta(prefs, &post);
+
+    Ok(render::layout_with_description(
+        prefs,
+        &title,
+        Some(&description),
         html! {
-            (render::post(post))
-            section p="8" bg="white" rounded shadow="md" x-data="{ open: false }" {
-                button x-show="!open" x-on:click="open = true" { "Reply" }
-                (render::reply_form_template(id))
-            }
+            @let closed = post.archived_at.is_some() || post.deleted_at.is_some();
+            (render::post(prefs, post))
+            (reply_section(prefs, id, closed, View::Flat))
             section flex="~ col items-start" gap="4" {
-                h2 font="size-5 bold" { "Replies" }
-                ul #{"replies-" (id)}
-                    ."empty-after-content-['No_replies_yet.']"
+                h2 font="size-5 bold" { (locale.text("replies")) }
+                (view_toggle(prefs, id, View::Flat))
+                ul #flat-replies
+                    data-empty=(locale.text("no-replies"))
                     flex="~ col self-stretch"
                     gap="4"
                     role="list"
                 {
                     @for reply in replies {
                         li flex="~ col" gap="4" {
-                            (render::reply(reply))
+                            (render::flat_reply(prefs, reply))
                         }
                     }
                 }
@@ -99,51 +232,241 @@ pub async fn get_replies(
     ))
 }
 
+/// The title and description of the page showing `post` and its replies.
+fn page_meta(prefs: &Preferences, post: &post::Model) -> (String, String) {
+    const DESCRIPTION_LENGTH: usize = 200;
+
+    let locale = prefs.locale();
+    let untitled = format!("clovers :: {}", locale.text("title-replies"));
+
+    if post.deleted_at.is_some() {
+        return (untitled, locale.text("deleted"));
+    }
+
+    let title = match &post.subject {
+        Some(subject) => format!("clovers :: {subject}"),
+        None => untitled,
+    };
+
+    let description = post.content.chars().take(DESCRIPTION_LENGTH).collect();
+
+    (title, description)
+}
+
+/// The button opening the form for replying to `id`, or a notice if it's `closed` to replies
+/// because it's archived or deleted.
+fn reply_section(prefs: &Preferences, id: i32, closed: bool, view: View) -> Markup {
+    let locale = prefs.locale();
+
+    html! {
+        @if closed {
+            section p="8" bg="$container-color" rounded shadow="md" {
+                (locale.text("closed-to-replies"))
+            }
+        } @else {
+            section p="8" bg="$container-color" rounded shadow="md" x-data="{ open: false }" {
+                button x-show="!open" x-on:click="open = true" { (locale.text("reply")) }
+                (render::reply_form_template(prefs, id, view))
+            }
+        }
+    }
+}
+
+/// Links switching the replies to `id` between the nested and flat views.
+fn view_toggle(prefs: &Preferences, id: i32, current: View) -> Markup {
+    let locale = prefs.locale();
+
+    html! {
+        nav flex="~ row" gap="2" {
+            @for view in [View::Nested, View::Flat] {
+                @if view == current {
+                    span font-bold { (locale.text(view.message_id())) }
+                } @else {
+                    (render::link(
+                        RepliesPath { id }.with_query_params(RepliesQuery {
+                            nested: false,
+                            view: Some(view),
+                        }),
+                        locale.text(view.message_id()),
+                    ))
+                }
+            }
+        }
+    }
+}
+
 pub async fn make_reply(
     RepliesPath { id }: RepliesPath,
     State(state): State<AppState>,
+    ClientIp(client): ClientIp,
+    jar: CookieJar,
+    prefs: Preferences,
     Form(post): Form<MakeReply>,
-) -> AppResult<Markup> {
-    if post.content.is_empty() {
-        return Ok(Markup::default());
-    }
+) -> AppResult<Response> {
+    let (jar, password) = super::post_password(jar, &post.password);
 
-    let Poster { name, hash } = post.poster.parse().expect("Infallible");
+    let result = state
+        .posts
+        .create(NewPost {
+            content: post.content,
+            poster: post.poster,
+            subject: None,
+            parent_post_id: Some(id),
+            client: Some(client),
+            password: Some(password),
+            signed: Signed::from_form(post.identity, post.signature, post.signed_at),
+        })
+        .await;
 
-    let post = post::ActiveModel {
-        content: ActiveValue::Set(post.content),
-        name: ActiveValue::Set(name),
-        hash: ActiveValue::Set(hash),
-        parent_post_id: ActiveValue::Set(Some(id)),
-        ..Default::default()
+    let post = match result {
+        Ok(post) => post,
+        Err(err) => {
+            return super::rejected_submission(&prefs, &format!("reply-{id}"), Field::REPLY, err)
+        }
     };
 
-    let post = Post::insert(post).exec_with_returning(&state.db).await?;
+    let rendered_reply = match prefs.view {
+        View::Nested => render::reply(&prefs, post, &mut ReplyTree::default()),
+        View::Flat => render::flat_reply(&prefs, post),
+    };
 
-    Ok(html! {
-        li.fade-in flex="~ col" gap="4" { (render::reply(post)) }
-    })
+    Ok((
+        jar,
+        html! {
+            li.fade-in flex="~ col" gap="4" { (rendered_reply) }
+        },
+    )
+        .into_response())
+}
+
+/// Every version of a post's content, newest first, each compared with the one before.
+pub async fn get_history(
+    HistoryPath { id }: HistoryPath,
+    State(state): State<AppState>,
+    prefs: Preferences,
+) -> AppResult<Markup> {
+    let post = Post::find_by_id(id)
+        .one(&state.db)
+        .await?
+        .filter(|post| post.deleted_at.is_none())
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Not Found: {id}")))?;
+
+    let revisions = post
+        .find_related(PostRevision)
+        .order_by_asc(post_revision::Column::Id)
+        .all(&state.db)
+        .await?;
+
+    // Oldest first, along with when each was written.
+    let mut versions: Vec<_> = revisions
+        .into_iter()
+        .map(|revision| (revision.content, revision.created_at))
+        .collect();
+    versions.push((post.content, post.edited_at.unwrap_or(post.created_at)));
+
+    let (original, posted_at) = &versions[0];
+    let locale = prefs.locale();
+
+    Ok(render::layout(
+        &prefs,
+        &format!("clovers :: {}", locale.text("title-history")),
+        html! {
+            section flex="~ col items-start" gap="4" {
+                h2 font="size-5 bold" {
+                    (locale.text("edit-history-of")) " " (render::link(RepliesPath { id }, html! { ">>" (id) }))
+                }
+                ol flex="~ col self-stretch" gap="4" role="list" {
+                    @for pair in versions.windows(2).rev() {
+                        li {
+                            article p="4" bg="$container-color" rounded shadow="md" flex="~ col" gap="2" {
+                                span { (locale.text("edited-at")) " " (render::relative_time(&prefs, pair[1].1)) }
+                                (render::revision_diff(&pair[0].0, &pair[1].0))
+                            }
+                        }
+                    }
+                    li {
+                        article p="4" bg="$container-color" rounded shadow="md" flex="~ col" gap="2" {
+                            span { (locale.text("posted")) " " (render::relative_time(&prefs, *posted_at)) }
+                            pre font-sans { (original) }
+                        }
+                    }
+                }
+            }
+        },
+    ))
+}
+
+pub async fn delete_post(
+    DeletePath { id }: DeletePath,
+    State(state): State<AppState>,
+    ClientIp(client): ClientIp,
+    jar: CookieJar,
+    prefs: Preferences,
+    Form(form): Form<DeletePost>,
+) -> AppResult<Response> {
+    let password = super::given_password(&jar, &form.password);
+
+    match state.posts.delete(id, password, client).await {
+        Ok(post) => Ok(rerender(&prefs, post).into_response()),
+        Err(err) => super::rejected_submission(&prefs, &format!("delete-{id}"), &[], err),
+    }
+}
+
+pub async fn edit_post(
+    EditPath { id }: EditPath,
+    State(state): State<AppState>,
+    ClientIp(client): ClientIp,
+    jar: CookieJar,
+    prefs: Preferences,
+    Form(form): Form<EditPost>,
+) -> AppResult<Response> {
+    let password = super::given_password(&jar, &form.password);
+
+    match state.posts.edit(id, password, &form.content, client).await {
+        Ok(post) => Ok(rerender(&prefs, post).into_response()),
+        Err(err) => {
+            super::rejected_submission(&prefs, &format!("edit-{id}"), &[Field::Content], err)
+        }
+    }
+}
+
+/// Renders a post that was just changed, in place of its old self.
+fn rerender(prefs: &Preferences, post: post::Model) -> Markup {
+    match post.parent_post_id {
+        None => render::post(prefs, post),
+        Some(_) => render::reply_article(prefs, &post, prefs.view),
+    }
 }
 
 pub async fn get_replies_lazy(
     RepliesLazyPath { id }: RepliesLazyPath,
     State(state): State<AppState>,
+    prefs: Preferences,
 ) -> AppResult<Markup> {
-    const LAZY_LIMIT: u64 = 4;
+    state.metrics.lazy_load();
 
-    let replies_path = RepliesPath { id }.with_query_params(RepliesQuery { nested: true });
+    let replies_path = RepliesPath { id }.with_query_params(RepliesQuery {
+        nested: true,
+        view: None,
+    });
 
-    let reply_count = Post::find().filter(post::Column::ParentPostId.eq(id)).count(&state.db).await?;
+    let reply_count = Post::find_by_id(id)
+        .select_only()
+        .column(post::Column::ReplyCount)
+        .into_tuple::<i32>()
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Not Found: {id}")))?;
 
     // Don't load too many replies
-    if reply_count > LAZY_LIMIT {
+    if reply_count > prefs.lazy_threshold {
         return Ok(html! {
             ul {
                 button
                     hx-get=(replies_path)
                     hx-target="closest ul"
                     hx-swap="outerHTML"
-                { "Load " (reply_count) " Replies" }
+                { (prefs.locale().text_with("load-replies", &fluent_args!["count" => reply_count])) }
             }
         })
     }
@@ -1,15 +1,29 @@
+use std::collections::HashMap;
+
 use axum::{
     extract::{Query, State},
     http::StatusCode,
 };
 use axum_extra::routing::TypedPath;
+use chrono::{DateTime, Utc};
+use fluent::fluent_args;
 use maud::{html, Markup};
-use sea_orm::{entity::*, query::*};
+use sea_orm::{
+    entity::*,
+    query::*,
+    sea_query::{Expr, Func, LikeExpr},
+    DatabaseConnection, DbErr,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     entities::{post, prelude::*},
-    render, AppResult, AppState,
+    identity,
+    preferences::Preferences,
+    profile::{PosterFilter, Profile, ACTIVITY_WEEKS},
+    render,
+    routes::replies::RepliesPath,
+    AppResult, AppState,
 };
 
 #[derive(TypedPath, Deserialize)]
@@ -21,39 +35,513 @@ pub struct UserPath {
 #[derive(Serialize, Deserialize)]
 pub struct UserQuery {
     pub hash: Option<String>,
+    /// Pages of posts start at 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u64>,
+}
+
+/// Every name used with a tripcode.
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/tripcode/:hash")]
+pub struct TripcodePath {
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TripcodeQuery {
+    /// Pages of names start at 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u64>,
+}
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/users")]
+pub struct UsersPath;
+
+/// Searches by `hash` if given, else by `name_like`; without either, lists the tripcodes that
+/// posted lately.
+#[derive(Serialize, Deserialize)]
+pub struct UsersQuery {
+    /// Finds every post made with a tripcode, whatever the name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// Finds the posters whose name contains this, ignoring case.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_like: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u64>,
 }
 
-pub async fn search_user(
+/// How many posts or posters are shown per page.
+const PAGE_SIZE: u64 = 20;
+
+/// How recently a tripcode must have posted to be listed in the directory.
+const ACTIVE_DAYS: i64 = 30;
+
+/// `page`, or the last page if it's past it, and how many pages there are. Past the last
+/// page, the offset would overflow.
+fn clamp_page(page: u64, pages: u64) -> (u64, u64) {
+    let pages = pages.max(1);
+
+    (page.clamp(1, pages), pages)
+}
+
+/// Decodes a tripcode hash, as found in links.
+fn decode_hash(hash: &str) -> AppResult<Vec<u8>> {
+    use base64ct::Encoding;
+
+    base64ct::Base64UrlUnpadded::decode_vec(hash)
+        .map_err(|_| (StatusCode::BAD_REQUEST, String::from("Invalid Hash")).into())
+}
+
+/// A poster's statistics and posts, newest first.
+pub async fn get_profile(
     UserPath { name }: UserPath,
     State(state): State<AppState>,
     Query(query): Query<UserQuery>,
+    prefs: Preferences,
 ) -> AppResult<Markup> {
-    use base64ct::Encoding;
+    let bytes = query.hash.as_deref().map(decode_hash).transpose()?;
+    let poster = PosterFilter {
+        name: &name,
+        hash: bytes.as_deref(),
+    };
 
-    let bytes = query
-        .hash
-        .as_deref()
-        .map(base64ct::Base64UrlUnpadded::decode_vec)
-        .transpose()
-        .map_err(|_| (StatusCode::BAD_REQUEST, String::from("Invalid Hash")))?;
+    let profile = Profile::load(&state.db, poster).await?;
+    let signed = !identity::fingerprints(&state.db, bytes.clone())
+        .await?
+        .is_empty();
 
+    let (page, pages) = clamp_page(query.page.unwrap_or(1), profile.posts.div_ceil(PAGE_SIZE));
     let posts = Post::find()
-        .filter(post::Column::Name.eq(&name))
-        .apply_if(bytes, |query, bytes| {
-            query.filter(post::Column::Hash.eq(bytes))
-        })
+        .filter(poster.condition())
         .order_by_desc(post::Column::Id)
-        .all(&state.db)
+        .paginate(&state.db, PAGE_SIZE)
+        .fetch_page(page - 1)
         .await?;
 
+    let page_path = |page| {
+        UserPath { name: name.clone() }
+            .with_query_params(UserQuery {
+                hash: query.hash.clone(),
+                page: Some(page),
+            })
+            .to_string()
+    };
+
+    let locale = prefs.locale();
+
+    Ok(render::layout(
+        &prefs,
+        &format!("clovers :: {name}"),
+        html! {
+            section p="8" bg="$container-color" rounded shadow="md" flex="~ col" gap="2" {
+                h2 font="size-6" { (render::poster(&name, query.hash.as_deref(), signed)) }
+                @if let (Some(first_seen), Some(last_seen)) = (profile.first_seen, profile.last_seen) {
+                    span {
+                        (locale.text("first-seen")) " " (render::relative_time(&prefs, first_seen))
+                        ", " (locale.text("last-seen")) " " (render::relative_time(&prefs, last_seen))
+                    }
+                }
+                span {
+                    (locale.text_with("post-count", &fluent_args!["count" => profile.posts]))
+                    ", "
+                    (locale.text_with("thread-count", &fluent_args!["count" => profile.threads]))
+                }
+                @if let Some(hash) = &query.hash {
+                    (render::link(TripcodePath { hash: hash.clone() }, locale.text("names-with-tripcode")))
+                }
+            }
+            section flex="~ col" gap="2" {
+                h3 font="size-5 bold" { (locale.text("activity")) }
+                (activity_histogram(&prefs, &profile.activity))
+            }
+            @if !profile.active_threads.is_empty() {
+                section flex="~ col items-start" gap="2" {
+                    h3 font="size-5 bold" { (locale.text("most-active-threads")) }
+                    ol role="list" flex="~ col" gap="1" {
+                        @for (thread, count) in &profile.active_threads {
+                            li {
+                                (render::link(RepliesPath { id: thread.id }, thread_title(&prefs, thread)))
+                                " (" (locale.text_with("post-count", &fluent_args!["count" => *count])) ")"
+                            }
+                        }
+                    }
+                }
+            }
+            section flex="~ col items-start" gap="4" {
+                h3 font="size-5 bold" { (locale.text("posts")) }
+                (render::posts(&prefs, posts))
+                (render::pagination(&prefs, page, pages, page_path))
+            }
+        },
+    ))
+}
+
+/// Every name posted under with a tripcode, most recently used first. Names are typed in
+/// freely, but only the holder of the secret can post with its tripcode.
+pub async fn get_tripcode_names(
+    TripcodePath { hash }: TripcodePath,
+    State(state): State<AppState>,
+    Query(query): Query<TripcodeQuery>,
+    prefs: Preferences,
+) -> AppResult<Markup> {
+    let bytes = decode_hash(&hash)?;
+
+    let paginator = Post::find()
+        .select_only()
+        .column(post::Column::Name)
+        .column_as(Expr::col(post::Column::Id).count(), "posts")
+        .column_as(Expr::col(post::Column::Id).max(), "last_post_id")
+        .filter(post::Column::Hash.eq(bytes.clone()))
+        .filter(post::Column::DeletedAt.is_null())
+        .group_by(post::Column::Name)
+        .order_by_desc(Expr::col(post::Column::Id).max())
+        .into_tuple::<(String, i64, i32)>()
+        .paginate(&state.db, PAGE_SIZE);
+
+    let (page, pages) = clamp_page(query.page.unwrap_or(1), paginator.num_pages().await?);
+    let names = paginator.fetch_page(page - 1).await?;
+
+    let last_posts = last_posts(&state.db, names.iter().map(|&(_, _, id)| id)).await?;
+    let signed = !identity::fingerprints(&state.db, [bytes.clone()])
+        .await?
+        .is_empty();
+
+    let page_path = |page| {
+        TripcodePath { hash: hash.clone() }
+            .with_query_params(TripcodeQuery { page: Some(page) })
+            .to_string()
+    };
+
+    let locale = prefs.locale();
+
     Ok(render::layout(
-        "clovers :: posts",
+        &prefs,
+        &format!("clovers :: {}", locale.text("title-tripcode")),
         html! {
-            span {
-                "Searching for posts by "
-                (render::poster(&name, query.hash.as_deref()))
+            section flex="~ col items-start" gap="4" {
+                h2 font="size-5 bold" {
+                    (locale.text("names-used-with")) " " (render::poster("", Some(&hash), signed))
+                }
+                ul role="list" flex="~ col" gap="2" {
+                    @for (name, posts, last_post_id) in names {
+                        @let last_post = last_posts.get(&last_post_id).map(|&(_, time)| time);
+                        (poster_row(&prefs, name, Some(&bytes), signed, posts, last_post))
+                    }
+                }
+                (render::pagination(&prefs, page, pages, page_path))
             }
-            (render::posts(posts))
         },
     ))
 }
+
+/// Finds posts by tripcode, or posters by name, or else lists the tripcodes that posted
+/// lately.
+pub async fn search_users(
+    _: UsersPath,
+    State(state): State<AppState>,
+    Query(query): Query<UsersQuery>,
+    prefs: Preferences,
+) -> AppResult<Markup> {
+    // Blank fields of the search form still get sent.
+    let hash = query
+        .hash
+        .as_deref()
+        .map(|hash| hash.trim().trim_start_matches(['#', '🔑']))
+        .filter(|hash| !hash.is_empty());
+    let name_like = query
+        .name_like
+        .as_deref()
+        .map(str::trim)
+        .filter(|name_like| !name_like.is_empty());
+    let page = query.page.unwrap_or(1);
+
+    let (results, page, pages) = match (hash, name_like) {
+        (Some(hash), _) => posts_by_tripcode(&state.db, &prefs, hash, page).await?,
+        (None, Some(name_like)) => posters_named_like(&state.db, &prefs, name_like, page).await?,
+        (None, None) => active_tripcodes(&state.db, &prefs, page).await?,
+    };
+
+    let page_path = |page| {
+        UsersPath
+            .with_query_params(UsersQuery {
+                hash: hash.map(str::to_owned),
+                name_like: name_like.map(str::to_owned),
+                page: Some(page),
+            })
+            .to_string()
+    };
+
+    let locale = prefs.locale();
+
+    Ok(render::layout(
+        &prefs,
+        &format!("clovers :: {}", locale.text("title-users")),
+        html! {
+            section p="8" bg="$container-color" rounded shadow="md" flex="~ col" gap="4" {
+                h2 font="size-5 bold" { (locale.text("users")) }
+                form method="get" action=(UsersPath) flex="~ row wrap items-end" gap="4" {
+                    label flex="~ col" {
+                        span { (locale.text("search-name-like")) }
+                        input type="search" name="name_like" value=[name_like] autocomplete="off";
+                    }
+                    label flex="~ col" {
+                        span { (locale.text("search-tripcode")) }
+                        input type="search" name="hash" value=[hash] autocomplete="off" font-mono;
+                    }
+                    button p="x-4 y-1" rounded bg="$primary-color" text="$on-primary-color" {
+                        (locale.text("search"))
+                    }
+                }
+            }
+            section flex="~ col items-start" gap="4" {
+                (results)
+                (render::pagination(&prefs, page, pages, page_path))
+            }
+        },
+    ))
+}
+
+/// Every post made with the tripcode `hash`, newest first.
+async fn posts_by_tripcode(
+    db: &DatabaseConnection,
+    prefs: &Preferences,
+    hash: &str,
+    page: u64,
+) -> AppResult<(Markup, u64, u64)> {
+    let bytes = decode_hash(hash)?;
+    let signed = !identity::fingerprints(db, [bytes.clone()])
+        .await?
+        .is_empty();
+
+    let paginator = Post::find()
+        .filter(post::Column::Hash.eq(bytes))
+        .filter(post::Column::DeletedAt.is_null())
+        .order_by_desc(post::Column::Id)
+        .paginate(db, PAGE_SIZE);
+
+    let (page, pages) = clamp_page(page, paginator.num_pages().await?);
+    let posts = paginator.fetch_page(page - 1).await?;
+
+    let locale = prefs.locale();
+
+    let results = html! {
+        h3 font="size-5 bold" {
+            (locale.text("posts-with-tripcode")) " " (render::poster("", Some(hash), signed))
+        }
+        (render::link(TripcodePath { hash: hash.to_owned() }, locale.text("names-with-tripcode")))
+        @if posts.is_empty() {
+            p text="$muted-color" { (locale.text("no-results")) }
+        }
+        (render::posts(prefs, posts))
+    };
+
+    Ok((results, page, pages))
+}
+
+/// Every name and tripcode pair whose name contains `name_like`, most recently used first.
+async fn posters_named_like(
+    db: &DatabaseConnection,
+    prefs: &Preferences,
+    name_like: &str,
+    page: u64,
+) -> AppResult<(Markup, u64, u64)> {
+    // Wildcards typed in are matched literally.
+    let escaped = name_like
+        .to_lowercase()
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    let paginator = Post::find()
+        .select_only()
+        .column(post::Column::Name)
+        .column(post::Column::Hash)
+        .column_as(Expr::col(post::Column::Id).count(), "posts")
+        .column_as(Expr::col(post::Column::Id).max(), "last_post_id")
+        .filter(
+            Expr::expr(Func::lower(Expr::col(post::Column::Name)))
+                .like(LikeExpr::new(format!("%{escaped}%")).escape('\\')),
+        )
+        .filter(post::Column::DeletedAt.is_null())
+        .group_by(post::Column::Name)
+        .group_by(post::Column::Hash)
+        .order_by_desc(Expr::col(post::Column::Id).max())
+        .into_tuple::<(String, Option<Vec<u8>>, i64, i32)>()
+        .paginate(db, PAGE_SIZE);
+
+    let (page, pages) = clamp_page(page, paginator.num_pages().await?);
+    let posters = paginator.fetch_page(page - 1).await?;
+    let last_posts = last_posts(db, posters.iter().map(|&(_, _, _, id)| id)).await?;
+    let fingerprints = identity::fingerprints(
+        db,
+        posters.iter().filter_map(|(_, hash, _, _)| hash.clone()),
+    )
+    .await?;
+
+    let locale = prefs.locale();
+
+    let results = html! {
+        h3 font="size-5 bold" {
+            (locale.text_with("posters-named-like", &fluent_args!["pattern" => name_like]))
+        }
+        ul role="list" flex="~ col" gap="2" data-empty=(locale.text("no-results")) {
+            @for (name, hash, posts, last_post_id) in posters {
+                @let last_post = last_posts.get(&last_post_id).map(|&(_, time)| time);
+                @let signed = hash.as_ref().is_some_and(|hash| fingerprints.contains(hash));
+                (poster_row(prefs, name, hash.as_deref(), signed, posts, last_post))
+            }
+        }
+    };
+
+    Ok((results, page, pages))
+}
+
+/// The tripcodes that posted in the last [`ACTIVE_DAYS`], under the name they last used,
+/// most recent first.
+async fn active_tripcodes(
+    db: &DatabaseConnection,
+    prefs: &Preferences,
+    page: u64,
+) -> AppResult<(Markup, u64, u64)> {
+    let since = Utc::now() - chrono::Duration::days(ACTIVE_DAYS);
+
+    let paginator = Post::find()
+        .select_only()
+        .column(post::Column::Hash)
+        .column_as(Expr::col(post::Column::Id).count(), "posts")
+        .column_as(Expr::col(post::Column::Id).max(), "last_post_id")
+        .filter(post::Column::Hash.is_not_null())
+        .filter(post::Column::DeletedAt.is_null())
+        .filter(post::Column::CreatedAt.gt(since))
+        .group_by(post::Column::Hash)
+        .order_by_desc(Expr::col(post::Column::Id).max())
+        .into_tuple::<(Vec<u8>, i64, i32)>()
+        .paginate(db, PAGE_SIZE);
+
+    let (page, pages) = clamp_page(page, paginator.num_pages().await?);
+    let tripcodes = paginator.fetch_page(page - 1).await?;
+    let mut last_posts = last_posts(db, tripcodes.iter().map(|&(_, _, id)| id)).await?;
+    let fingerprints =
+        identity::fingerprints(db, tripcodes.iter().map(|(hash, _, _)| hash.clone())).await?;
+
+    let locale = prefs.locale();
+
+    let results = html! {
+        h3 font="size-5 bold" { (locale.text("active-tripcodes")) }
+        p text="$muted-color" {
+            (locale.text_with("active-tripcodes-description", &fluent_args!["days" => ACTIVE_DAYS]))
+        }
+        ul role="list" flex="~ col" gap="2" data-empty=(locale.text("no-results")) {
+            @for (hash, posts, last_post_id) in tripcodes {
+                @if let Some((name, last_post)) = last_posts.remove(&last_post_id) {
+                    @let signed = fingerprints.contains(&hash);
+                    (poster_row(prefs, name, Some(&hash), signed, posts, Some(last_post)))
+                }
+            }
+        }
+    };
+
+    Ok((results, page, pages))
+}
+
+/// The name and time of each of the posts `ids`.
+async fn last_posts(
+    db: &DatabaseConnection,
+    ids: impl IntoIterator<Item = i32>,
+) -> Result<HashMap<i32, (String, DateTime<Utc>)>, DbErr> {
+    Ok(Post::find()
+        .select_only()
+        .column(post::Column::Id)
+        .column(post::Column::Name)
+        .column(post::Column::CreatedAt)
+        .filter(post::Column::Id.is_in(ids))
+        .into_tuple::<(i32, String, DateTime<Utc>)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|(id, name, created_at)| (id, (name, created_at)))
+        .collect())
+}
+
+/// A poster, linking to their profile, with how many posts they made and when they last did.
+fn poster_row(
+    prefs: &Preferences,
+    name: String,
+    hash: Option<&[u8]>,
+    signed: bool,
+    posts: i64,
+    last_post: Option<DateTime<Utc>>,
+) -> Markup {
+    let locale = prefs.locale();
+
+    html! {
+        li {
+            (render::poster_link(name, hash, signed))
+            " (" (locale.text_with("post-count", &fluent_args!["count" => posts]))
+            @if let Some(last_post) = last_post {
+                ", " (locale.text("last-used")) " " (render::relative_time(prefs, last_post))
+            }
+            ")"
+        }
+    }
+}
+
+/// Bars of how many posts were made each week, oldest first.
+fn activity_histogram(prefs: &Preferences, activity: &[u64]) -> Markup {
+    let locale = prefs.locale();
+    let busiest = activity.iter().copied().max().unwrap_or_default().max(1);
+    let description = locale.text_with(
+        "activity-description",
+        &fluent_args!["weeks" => ACTIVITY_WEEKS],
+    );
+
+    html! {
+        figure flex="~ col" gap="2" {
+            div
+                role="img"
+                aria-label=(description)
+                h="24"
+                p="2"
+                bg="$container-color"
+                rounded
+                shadow="md"
+                flex="~ row items-end"
+                gap="1"
+            {
+                @for &count in activity {
+                    // Dynamic heights can't be attributify classes.
+                    div
+                        flex="1"
+                        bg="$primary-color"
+                        rounded="t"
+                        style=(format!("height: max({}%, 2px)", count * 100 / busiest))
+                        title=(locale.text_with("post-count", &fluent_args!["count" => count]))
+                    { }
+                }
+            }
+            figcaption text="sm $muted-color" { (description) }
+        }
+    }
+}
+
+/// What a thread is called in lists: its subject, or else the start of its content.
+fn thread_title(prefs: &Preferences, thread: &post::Model) -> String {
+    const TITLE_LENGTH: usize = 60;
+
+    if thread.deleted_at.is_some() {
+        return prefs.locale().text("deleted");
+    }
+
+    if let Some(subject) = &thread.subject {
+        return subject.clone();
+    }
+
+    let mut title: String = thread.content.chars().take(TITLE_LENGTH).collect();
+    if title.len() < thread.content.len() {
+        title.push('…');
+    }
+
+    title
+}
@@ -0,0 +1,193 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::*, query::*, sea_query::Expr, ConnectionTrait, DbErr};
+
+use crate::entities::{post, prelude::*};
+
+/// What deleting a post takes out of its thread.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Removal {
+    /// The post stays as a tombstone, and the replies to it stay in place.
+    Tombstone,
+    /// The post is gone, along with every reply to it.
+    Subtree,
+}
+
+/// Updates the denormalized statistics of every ancestor of a newly inserted reply.
+///
+/// Must run in the same transaction as the insert, so the counts never drift.
+pub async fn record_reply<C: ConnectionTrait>(db: &C, reply: &post::Model) -> Result<(), DbErr> {
+    let Some(parent_id) = reply.parent_post_id else {
+        return Ok(());
+    };
+
+    Post::update_many()
+        .col_expr(
+            post::Column::ReplyCount,
+            Expr::col(post::Column::ReplyCount).add(1),
+        )
+        .filter(post::Column::Id.eq(parent_id))
+        .exec(db)
+        .await?;
+
+    let mut ancestor = Some(parent_id);
+
+    while let Some(id) = ancestor {
+        Post::update_many()
+            .col_expr(
+                post::Column::DescendantCount,
+                Expr::col(post::Column::DescendantCount).add(1),
+            )
+            .col_expr(post::Column::LastReplyAt, Expr::value(reply.created_at))
+            .filter(post::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+
+        ancestor = parent_of(db, id).await?;
+    }
+
+    Ok(())
+}
+
+/// Updates the denormalized statistics of every ancestor of `post`, as it was before being
+/// deleted. Deleted posts no longer count as replies, nor bump their thread. A tombstone
+/// still counts as a reply to its parent though, so the replies to it get loaded.
+///
+/// Must run in the same transaction as the deletion, after it, and only once per post.
+pub async fn record_deletion<C: ConnectionTrait>(
+    db: &C,
+    post: &post::Model,
+    removal: Removal,
+) -> Result<(), DbErr> {
+    let Some(parent_id) = post.parent_post_id else {
+        return Ok(());
+    };
+
+    if removal == Removal::Subtree {
+        Post::update_many()
+            .col_expr(
+                post::Column::ReplyCount,
+                Expr::col(post::Column::ReplyCount).sub(1),
+            )
+            .filter(post::Column::Id.eq(parent_id))
+            .exec(db)
+            .await?;
+    }
+
+    // Tombstones in the subtree were already taken off when they were deleted.
+    let removed = match removal {
+        Removal::Tombstone => 1,
+        Removal::Subtree => i32::from(post.deleted_at.is_none()) + post.descendant_count,
+    };
+
+    let mut ancestor = Some(parent_id);
+
+    while let Some(id) = ancestor {
+        let last_reply_at = last_reply_at(db, id).await?;
+
+        Post::update_many()
+            .col_expr(
+                post::Column::DescendantCount,
+                Expr::col(post::Column::DescendantCount).sub(removed),
+            )
+            .col_expr(post::Column::LastReplyAt, Expr::value(last_reply_at))
+            .filter(post::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+
+        ancestor = parent_of(db, id).await?;
+    }
+
+    Ok(())
+}
+
+/// When the latest reply left under the post `id` was made, from its direct replies, which
+/// are up to date.
+async fn last_reply_at<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+) -> Result<Option<DateTime<Utc>>, DbErr> {
+    type Date = Option<DateTime<Utc>>;
+
+    let replies = Post::find()
+        .select_only()
+        .columns([
+            post::Column::CreatedAt,
+            post::Column::DeletedAt,
+            post::Column::LastReplyAt,
+        ])
+        .filter(post::Column::ParentPostId.eq(id))
+        .into_tuple::<(DateTime<Utc>, Date, Date)>()
+        .all(db)
+        .await?;
+
+    Ok(replies
+        .into_iter()
+        .flat_map(|(created_at, deleted_at, last_reply_at)| {
+            let created_at = deleted_at.is_none().then_some(created_at);
+            [created_at, last_reply_at]
+        })
+        .flatten()
+        .max())
+}
+
+async fn parent_of<C: ConnectionTrait>(db: &C, id: i32) -> Result<Option<i32>, DbErr> {
+    Ok(Post::find_by_id(id)
+        .select_only()
+        .column(post::Column::ParentPostId)
+        .into_tuple::<Option<i32>>()
+        .one(db)
+        .await?
+        .flatten())
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::DatabaseConnection;
+
+    use super::*;
+    use crate::database;
+
+    async fn reply(db: &DatabaseConnection, parent_post_id: Option<i32>) -> post::Model {
+        let post = post::ActiveModel {
+            content: ActiveValue::Set(String::from("content")),
+            name: ActiveValue::Set(String::from("name")),
+            parent_post_id: ActiveValue::Set(parent_post_id),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..Default::default()
+        };
+
+        let post = Post::insert(post).exec_with_returning(db).await.unwrap();
+        record_reply(db, &post).await.unwrap();
+        post
+    }
+
+    async fn stored(db: &DatabaseConnection, id: i32) -> post::Model {
+        Post::find_by_id(id).one(db).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn removing_a_subtree_takes_off_every_reply_in_it() {
+        let db = database::connect_test().await;
+        let thread = reply(&db, None).await;
+        let kept = reply(&db, Some(thread.id)).await;
+        let removed = reply(&db, Some(thread.id)).await;
+        reply(&db, Some(removed.id)).await;
+        reply(&db, Some(removed.id)).await;
+
+        let removed = stored(&db, removed.id).await;
+        Post::delete_many()
+            .filter(post::Column::ParentPostId.eq(removed.id))
+            .exec(&db)
+            .await
+            .unwrap();
+        Post::delete_by_id(removed.id).exec(&db).await.unwrap();
+        record_deletion(&db, &removed, Removal::Subtree)
+            .await
+            .unwrap();
+
+        let thread = stored(&db, thread.id).await;
+        assert_eq!(thread.reply_count, 1);
+        assert_eq!(thread.descendant_count, 1);
+        assert_eq!(thread.last_reply_at, Some(kept.created_at));
+    }
+}
@@ -0,0 +1,343 @@
+use unicode_normalization::UnicodeNormalization;
+
+use fluent::fluent_args;
+
+use crate::{config::Config, i18n::Locale, post_service::Rejection, poster::Poster};
+
+/// A form field a submission can be rejected for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Subject,
+    Content,
+}
+
+impl Field {
+    /// The fields of the form starting a thread.
+    pub const POST: &[Self] = &[Self::Name, Self::Subject, Self::Content];
+    /// The fields of the reply form, which has no subject.
+    pub const REPLY: &[Self] = &[Self::Name, Self::Content];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Subject => "subject",
+            Self::Content => "content",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FieldError {
+    pub field: Field,
+    pub reason: Rejection,
+    /// The most characters the field may have, if it had too many.
+    pub max_length: Option<usize>,
+}
+
+impl FieldError {
+    /// Why the field was rejected, in `locale`.
+    pub fn message(&self, locale: Locale) -> String {
+        let id = match (self.field, self.reason) {
+            (Field::Name, Rejection::TooLong) => "error-name-too-long",
+            (Field::Name, _) => "error-name-invalid-characters",
+            (Field::Subject, Rejection::TooLong) => "error-subject-too-long",
+            (Field::Subject, _) => "error-subject-invalid-characters",
+            (Field::Content, Rejection::EmptyContent) => "error-content-empty",
+            (Field::Content, Rejection::TooLong) => "error-content-too-long",
+            (Field::Content, _) => "error-content-invalid-characters",
+        };
+
+        match self.max_length {
+            Some(max) => locale.text_with(id, &fluent_args!["max" => max]),
+            None => locale.text(id),
+        }
+    }
+}
+
+/// How long names, subjects and content may be, in characters.
+#[derive(Clone, Copy)]
+pub struct Limits {
+    pub max_name_length: usize,
+    pub max_subject_length: usize,
+    pub max_content_length: usize,
+}
+
+impl Limits {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_name_length: config.max_name_length,
+            max_subject_length: config.max_subject_length,
+            max_content_length: config.max_content_length,
+        }
+    }
+}
+
+/// A submission's fields, cleaned up and checked.
+pub struct Valid {
+    /// The name trimmed and normalized, with the tripcode of the name as typed.
+    pub poster: Poster,
+    /// `None` if left blank.
+    pub subject: Option<String>,
+    pub content: String,
+}
+
+/// Cleans up a submission's fields, then checks them against `limits`.
+///
+/// Text is NFC-normalized and trimmed, and line endings become `\n`. Tripcodes are still
+/// hashed from the name and secret exactly as typed, so existing tripcodes keep their hash.
+pub fn validate(
+    limits: Limits,
+    poster: &str,
+    subject: Option<&str>,
+    content: &str,
+) -> Result<Valid, Vec<FieldError>> {
+    let raw_name = poster.split_once('#').map_or(poster, |(name, _)| name);
+
+    let name = raw_name.nfc().collect::<String>().trim().to_owned();
+    let subject = subject
+        .map(|subject| subject.nfc().collect::<String>().trim().to_owned())
+        .filter(|subject| !subject.is_empty());
+    let content = clean_content(content);
+
+    let mut errors = Vec::new();
+
+    if name.chars().count() > limits.max_name_length {
+        errors.push(FieldError {
+            field: Field::Name,
+            reason: Rejection::TooLong,
+            max_length: Some(limits.max_name_length),
+        });
+    }
+
+    if name.chars().any(|c| c.is_control() || is_invisible(c)) {
+        errors.push(FieldError {
+            field: Field::Name,
+            reason: Rejection::InvalidCharacters,
+            max_length: None,
+        });
+    }
+
+    if let Some(subject) = &subject {
+        if subject.chars().count() > limits.max_subject_length {
+            errors.push(FieldError {
+                field: Field::Subject,
+                reason: Rejection::TooLong,
+                max_length: Some(limits.max_subject_length),
+            });
+        }
+
+        if subject.chars().any(char::is_control) {
+            errors.push(FieldError {
+                field: Field::Subject,
+                reason: Rejection::InvalidCharacters,
+                max_length: None,
+            });
+        }
+    }
+
+    check_content(limits, &content, &mut errors);
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut poster: Poster = poster.parse().expect("Infallible");
+    // Names left blank once trimmed are anonymous.
+    poster.name = if name.is_empty() {
+        String::from(Poster::DEFAULT_NAME)
+    } else {
+        name
+    };
+
+    Ok(Valid {
+        poster,
+        subject,
+        content,
+    })
+}
+
+/// Cleans up and checks the new content of an edited post.
+pub fn validate_content(limits: Limits, content: &str) -> Result<String, Vec<FieldError>> {
+    let content = clean_content(content);
+
+    let mut errors = Vec::new();
+    check_content(limits, &content, &mut errors);
+
+    if errors.is_empty() {
+        Ok(content)
+    } else {
+        Err(errors)
+    }
+}
+
+fn clean_content(content: &str) -> String {
+    content
+        .replace("\r\n", "\n")
+        .nfc()
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+fn check_content(limits: Limits, content: &str, errors: &mut Vec<FieldError>) {
+    if content.is_empty() {
+        errors.push(FieldError {
+            field: Field::Content,
+            reason: Rejection::EmptyContent,
+            max_length: None,
+        });
+    }
+
+    if content.chars().count() > limits.max_content_length {
+        errors.push(FieldError {
+            field: Field::Content,
+            reason: Rejection::TooLong,
+            max_length: Some(limits.max_content_length),
+        });
+    }
+
+    if content
+        .chars()
+        .any(|c| c.is_control() && c != '\n' && c != '\t')
+    {
+        errors.push(FieldError {
+            field: Field::Content,
+            reason: Rejection::InvalidCharacters,
+            max_length: None,
+        });
+    }
+}
+
+/// Characters that render as nothing, or reorder the text around them, which lets a name
+/// pass for a different one.
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}' // soft hyphen
+            | '\u{034F}' // combining grapheme joiner
+            | '\u{061C}' // arabic letter mark
+            | '\u{115F}' | '\u{1160}' // hangul fillers
+            | '\u{180E}' // mongolian vowel separator
+            | '\u{200B}'..='\u{200F}' // zero-width characters, LRM, RLM
+            | '\u{202A}'..='\u{202E}' // bidi embeddings and overrides
+            | '\u{2060}'..='\u{2064}' // word joiner, invisible operators
+            | '\u{2066}'..='\u{2069}' // bidi isolates
+            | '\u{3164}' // hangul filler
+            | '\u{FEFF}' // zero-width no-break space
+            | '\u{FFA0}' // halfwidth hangul filler
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: Limits = Limits {
+        max_name_length: 4,
+        max_subject_length: 6,
+        max_content_length: 8,
+    };
+
+    fn rejections<T>(result: Result<T, Vec<FieldError>>) -> Vec<(Field, Rejection)> {
+        match result {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors
+                .into_iter()
+                .map(|error| (error.field, error.reason))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn counts_characters_against_the_limits() {
+        // Four characters, but eight bytes.
+        let valid = validate(LIMITS, "éééé", Some("ssssss"), "cccccccc").unwrap();
+        assert_eq!(valid.poster.name, "éééé");
+
+        let Err(errors) = validate(LIMITS, "nnnnn", Some("sssssss"), "ccccccccc") else {
+            panic!("too long fields were accepted");
+        };
+        let limits: Vec<_> = errors
+            .iter()
+            .map(|error| (error.field, error.reason, error.max_length))
+            .collect();
+        assert_eq!(
+            limits,
+            [
+                (Field::Name, Rejection::TooLong, Some(4)),
+                (Field::Subject, Rejection::TooLong, Some(6)),
+                (Field::Content, Rejection::TooLong, Some(8)),
+            ]
+        );
+
+        assert!(validate_content(LIMITS, "cccccccc").is_ok());
+        assert_eq!(
+            rejections(validate_content(LIMITS, "ccccccccc")),
+            [(Field::Content, Rejection::TooLong)]
+        );
+    }
+
+    #[test]
+    fn normalizes_and_trims_text() {
+        let valid = validate(
+            LIMITS,
+            " Ame\u{301} ",
+            Some(" Cafe\u{301} "),
+            "\r\n b\r\na\u{30a} \n",
+        )
+        .unwrap();
+
+        assert_eq!(valid.poster.name, "Amé");
+        assert_eq!(valid.subject.as_deref(), Some("Café"));
+        assert_eq!(valid.content, "b\n\u{e5}");
+
+        let edited = validate_content(LIMITS, " a\u{30a}\r\n").unwrap();
+        assert_eq!(edited, "\u{e5}");
+    }
+
+    #[test]
+    fn leaves_blanks_anonymous_and_subjectless() {
+        let valid = validate(LIMITS, "  ", Some("  "), "content").unwrap();
+
+        assert_eq!(valid.poster.name, Poster::DEFAULT_NAME);
+        assert_eq!(valid.poster.hash, None);
+        assert_eq!(valid.subject, None);
+    }
+
+    #[test]
+    fn hashes_tripcodes_from_the_name_as_typed() {
+        let valid = validate(LIMITS, " Ame\u{301} #secret", None, "content").unwrap();
+
+        assert_eq!(valid.poster.name, "Amé");
+        assert_eq!(
+            valid.poster.hash,
+            Poster::with_name_and_secret(" Ame\u{301} ", "secret").hash
+        );
+    }
+
+    #[test]
+    fn rejects_empty_content() {
+        assert_eq!(
+            rejections(validate(LIMITS, "", None, " \r\n\t ")),
+            [(Field::Content, Rejection::EmptyContent)]
+        );
+    }
+
+    #[test]
+    fn rejects_invisible_and_control_characters() {
+        assert_eq!(
+            rejections(validate(LIMITS, "a\u{200B}b", None, "content")),
+            [(Field::Name, Rejection::InvalidCharacters)]
+        );
+        assert_eq!(
+            rejections(validate(LIMITS, "name", Some("a\u{7}"), "content")),
+            [(Field::Subject, Rejection::InvalidCharacters)]
+        );
+        assert_eq!(
+            rejections(validate(LIMITS, "name", None, "a\u{0}b")),
+            [(Field::Content, Rejection::InvalidCharacters)]
+        );
+        assert!(validate(LIMITS, "name", None, "a\n\tb").is_ok());
+    }
+}
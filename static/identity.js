@@ -0,0 +1,135 @@
+// Lets posters sign with an identity instead of a tripcode: an Ed25519 key pair made here, of
+// which only the public key is sent to the server. Follows the same rules as `identity.rs`.
+//
+// The private key is made non-extractable and kept in IndexedDB, so not even a script running
+// on the page can read it out. Local storage only keeps the fingerprint, which pages need
+// right away.
+(() => {
+    const STORAGE_KEY = "identity";
+    const ALGORITHM = { name: "Ed25519" };
+    const DATABASE = "clovers";
+    const KEYS = "keys";
+
+    function encode(bytes) {
+        return btoa(String.fromCharCode(...new Uint8Array(bytes)))
+            .replace(/\+/g, "-")
+            .replace(/\//g, "_")
+            .replace(/=+$/, "");
+    }
+
+    function stored() {
+        try {
+            return JSON.parse(localStorage.getItem(STORAGE_KEY));
+        } catch {
+            return null;
+        }
+    }
+
+    // Runs `use` on the store of private keys, resolving to the result of the request it makes.
+    function withKeys(mode, use) {
+        return new Promise((resolve, reject) => {
+            const open = indexedDB.open(DATABASE, 1);
+            open.onupgradeneeded = () => open.result.createObjectStore(KEYS);
+            open.onerror = () => reject(open.error);
+            open.onsuccess = () => {
+                const db = open.result;
+                const transaction = db.transaction(KEYS, mode);
+                const request = use(transaction.objectStore(KEYS));
+                transaction.oncomplete = () => {
+                    db.close();
+                    resolve(request.result);
+                };
+                transaction.onerror = transaction.onabort = () => {
+                    db.close();
+                    reject(transaction.error);
+                };
+            };
+        });
+    }
+
+    window.identity = {
+        // The fingerprint of this browser's identity, if it has one.
+        fingerprint() {
+            return stored()?.fingerprint ?? null;
+        },
+
+        // Makes a key pair and registers its public key, returning its fingerprint.
+        async create() {
+            // Public keys can always be exported, whatever the key pair is made with.
+            const keys = await crypto.subtle.generateKey(ALGORITHM, false, ["sign", "verify"]);
+            const publicKey = await crypto.subtle.exportKey("raw", keys.publicKey);
+
+            const response = await fetch("/identities", {
+                method: "POST",
+                body: new URLSearchParams({ public_key: encode(publicKey) }),
+            });
+            if (!response.ok) {
+                throw new Error(await response.text());
+            }
+
+            const fingerprint = await response.text();
+            await withKeys("readwrite", (store) => store.put(keys.privateKey, STORAGE_KEY));
+            localStorage.setItem(STORAGE_KEY, JSON.stringify({ fingerprint }));
+
+            return fingerprint;
+        },
+
+        // There's no getting it back: only this browser ever had the private key.
+        forget() {
+            localStorage.removeItem(STORAGE_KEY);
+            withKeys("readwrite", (store) => store.delete(STORAGE_KEY)).catch(console.error);
+        },
+    };
+
+    // The fields to send instead of the form's own, for forms that were just signed.
+    const signatures = new WeakMap();
+
+    async function sign(form) {
+        const { fingerprint } = stored();
+        const key = await withKeys("readonly", (store) => store.get(STORAGE_KEY));
+        if (!key) {
+            throw new Error("the private key of this identity is gone");
+        }
+
+        // The identity stands in for a tripcode, so a secret typed after the name isn't sent.
+        const poster = form.elements.poster.value.split("#")[0];
+        const subject = form.elements.subject?.value ?? "";
+        const content = form.elements.content.value.replace(/\r\n/g, "\n");
+        const signedAt = Math.floor(Date.now() / 1000).toString();
+        const parent = form.dataset.parentPostId ?? "";
+
+        const message = `clovers post\n${parent}\n${signedAt}\n${poster}\n${subject}\n${content}`;
+        const signature = await crypto.subtle.sign(ALGORITHM, key, new TextEncoder().encode(message));
+
+        return { poster, identity: fingerprint, signature: encode(signature), signed_at: signedAt };
+    }
+
+    // Signing is asynchronous, so the request is held back until it's done.
+    document.addEventListener("htmx:confirm", (event) => {
+        const form = event.detail.elt;
+        const checkbox = form.querySelector?.("[data-sign]");
+        if (!checkbox?.checked || !stored()) {
+            return;
+        }
+
+        event.preventDefault();
+        sign(form).then(
+            (fields) => {
+                signatures.set(form, fields);
+                event.detail.issueRequest();
+            },
+            (error) => {
+                console.error(error);
+                alert(checkbox.dataset.signError);
+            },
+        );
+    });
+
+    document.addEventListener("htmx:configRequest", (event) => {
+        const fields = signatures.get(event.detail.elt);
+        if (fields) {
+            signatures.delete(event.detail.elt);
+            Object.assign(event.detail.parameters, fields);
+        }
+    });
+})();
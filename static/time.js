@@ -0,0 +1,56 @@
+// Keeps the timestamps made by `render::relative_time` current while the page stays open, and
+// words them in the reader's language and timezone. Follows the same rules as `Relative`.
+(() => {
+    const root = document.documentElement;
+
+    // Lets the server show times in this timezone too, unless another one was picked.
+    const timeZone = Intl.DateTimeFormat().resolvedOptions().timeZone;
+    if (timeZone && !document.cookie.split("; ").includes(`detected_timezone=${encodeURIComponent(timeZone)}`)) {
+        document.cookie = `detected_timezone=${encodeURIComponent(timeZone)}; path=/; max-age=31536000; samesite=lax`;
+    }
+
+    const locale = root.lang || undefined;
+    const relative = new Intl.RelativeTimeFormat(locale, { numeric: "auto" });
+    const absolute = new Intl.DateTimeFormat(locale, {
+        dateStyle: "medium",
+        timeStyle: "medium",
+        timeZone: root.dataset.timezone || undefined,
+    });
+
+    const units = [
+        ["week", 7 * 24 * 60 * 60],
+        ["day", 24 * 60 * 60],
+        ["hour", 60 * 60],
+        ["minute", 60],
+    ];
+
+    function describe(date) {
+        const seconds = (date.getTime() - Date.now()) / 1000;
+        const distance = Math.abs(seconds);
+
+        if (distance >= 4 * units[0][1]) {
+            return absolute.format(date);
+        }
+
+        for (const [unit, length] of units) {
+            if (distance >= length) {
+                return relative.format(Math.trunc(seconds / length), unit);
+            }
+        }
+
+        return distance < 2 ? relative.format(0, "second") : relative.format(Math.trunc(seconds), "second");
+    }
+
+    function refresh(root) {
+        for (const time of root.querySelectorAll("time[data-relative]")) {
+            const date = new Date(time.dateTime);
+            time.textContent = describe(date);
+            time.title = absolute.format(date);
+        }
+    }
+
+    document.addEventListener("DOMContentLoaded", () => refresh(document));
+    // Replies and posts swapped in by htmx.
+    document.addEventListener("htmx:load", (event) => refresh(event.target));
+    setInterval(() => refresh(document), 30 * 1000);
+})();